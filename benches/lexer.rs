@@ -0,0 +1,68 @@
+//! Lexing time grows linearly with the input: peeking clones the `CharIndices` iterator
+//! instead of walking the input with `chars().nth`, so every char is decoded once.
+//!
+//! `cargo bench --bench lexer`, time per input size in lines of `SNIPPET`:
+//!
+//! | lines   | `chars().nth` | linear peeking | ascii fast path |
+//! |---------|---------------|----------------|-----------------|
+//! | 1000    | 355 ms        | 632 µs         | 622 µs          |
+//! | 10000   | 16.7 s        | 6.55 ms        | 6.34 ms         |
+//! | 100000  | > 10 min      | 96.8 ms        | 96.3 ms         |
+//!
+//! "`chars().nth`" is the lexer with `peek_char` and `prev_char` rescanning the input
+//! from its start, as they did before peeking was fixed, so 10 times the lines take
+//! ~50 times longer. It was timed outside criterion since it's far too slow for it.
+//!
+//! The ascii fast path skips runs of spaces, tabs, letters and digits by looking at the
+//! bytes in `skip_whitespace`, identifiers and numbers instead of going through `next_char`
+//! for each char. Its gain is within the noise on `SNIPPET`, where strings, comments and
+//! interning dominate, and ~10% on indented lines of identifiers and numbers (`plain`):
+//!
+//! | lines   | linear peeking | ascii fast path |
+//! |---------|----------------|-----------------|
+//! | 50000   | 24.3 ms        | 21.9 ms         |
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use isy::lexer::tokenize;
+
+// a few lines using most of the token kinds, repeated to build inputs of any size
+const SNIPPET: &str = r#"// compute something
+let total: int = 0
+mut ratio := 1.5e3 * (total + 0xff) / 2
+name := "hello {total}!\n"
+if ratio >= 10 && !done { total += 1 } else { total -= 1 }
+/* block /* nested */ comment */
+fn add(a: int, b: int) -> int { return a + b }
+"#;
+
+// identifiers, numbers and plenty of spaces, where the ascii fast path matters the most
+const PLAIN_LINE: &str =
+    "        some_long_identifier_name another_identifier    12345678 // trailing comment\n";
+
+fn generate(lines: usize) -> String {
+    let snippet_lines = SNIPPET.lines().count();
+    SNIPPET.repeat(lines / snippet_lines)
+}
+
+fn bench_tokenize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tokenize");
+    for lines in [1_000, 10_000, 100_000].iter() {
+        let input = generate(*lines);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &input, |b, input| {
+            b.iter(|| tokenize(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_tokenize_plain(c: &mut Criterion) {
+    let input = PLAIN_LINE.repeat(50_000);
+    let mut group = c.benchmark_group("tokenize_plain");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("50000", |b| b.iter(|| tokenize(&input).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_tokenize, bench_tokenize_plain);
+criterion_main!(benches);
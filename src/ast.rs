@@ -1 +1,286 @@
+use std::fmt;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Ident(String),
+    Prefix {
+        op: PrefixOp,
+        right: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `callee(arg1, arg2)`
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// `target[index]`
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// `object.field`
+    Member {
+        object: Box<Expr>,
+        field: String,
+    },
+    Block(Block),
+    /// `if cond { ... } else { ... }`, `else_` is a `Block` or another `If`
+    If {
+        cond: Box<Expr>,
+        then: Block,
+        else_: Option<Box<Expr>>,
+    },
+}
+
+/// `{ a := 1; b := 2; a + b }`, its value is the trailing expression
+/// when it's not followed by `;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub value: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// `x := 1`, `x: int = 1`, `let x := 1` or `const X: int = 1`
+    Decl {
+        name: String,
+        typ: Option<String>,
+        value: Expr,
+        constant: bool,
+    },
+    /// `x = 1`, `x` must be already declared
+    Assign {
+        name: String,
+        value: Expr,
+    },
+    /// `while cond { ... }`
+    While {
+        cond: Expr,
+        body: Block,
+    },
+    Break,
+    Continue,
+    /// `fn add(a: int, b: int) -> int { a + b }`
+    FnDecl {
+        name: String,
+        params: Vec<Param>,
+        return_type: Option<String>,
+        body: Block,
+    },
+    Expr(Expr),
+}
+
+/// A function parameter, `a` or `a: int`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub typ: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrefixOp {
+    /// `-x`
+    Neg,
+    /// `!x`
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Int(value) => write!(f, "{}", value),
+            Expr::Float(value) => write!(f, "{:?}", value),
+            Expr::Bool(value) => write!(f, "{}", value),
+            Expr::Str(value) => write!(f, "{:?}", value),
+            Expr::Ident(name) => write!(f, "{}", name),
+            Expr::Prefix { op, right } => write!(f, "({}{})", op, right),
+            Expr::Binary { op, left, right } => write!(f, "({} {} {})", left, op, right),
+            Expr::Call { callee, args } => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", callee, args.join(", "))
+            }
+            Expr::Index { target, index } => write!(f, "{}[{}]", target, index),
+            Expr::Member { object, field } => write!(f, "{}.{}", object, field),
+            Expr::Block(block) => write!(f, "{}", block),
+            Expr::If { cond, then, else_ } => {
+                write!(f, "if {} {}", cond, then)?;
+                match else_ {
+                    Some(else_) => write!(f, " else {}", else_),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut items: Vec<String> = self.stmts.iter().map(|stmt| format!("{};", stmt)).collect();
+        if let Some(value) = &self.value {
+            items.push(value.to_string());
+        }
+
+        if items.is_empty() {
+            write!(f, "{{}}")
+        } else {
+            write!(f, "{{ {} }}", items.join(" "))
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stmt::Decl {
+                name,
+                typ,
+                value,
+                constant,
+            } => {
+                if *constant {
+                    write!(f, "const ")?;
+                }
+
+                match typ {
+                    Some(typ) => write!(f, "{}: {} = {}", name, typ, value),
+                    None => write!(f, "{} := {}", name, value),
+                }
+            }
+            Stmt::Assign { name, value } => write!(f, "{} = {}", name, value),
+            Stmt::While { cond, body } => write!(f, "while {} {}", cond, body),
+            Stmt::Break => write!(f, "break"),
+            Stmt::Continue => write!(f, "continue"),
+            Stmt::FnDecl {
+                name,
+                params,
+                return_type,
+                body,
+            } => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "fn {}({})", name, params.join(", "))?;
+                if let Some(return_type) = return_type {
+                    write!(f, " -> {}", return_type)?;
+                }
+                write!(f, " {}", body)
+            }
+            Stmt::Expr(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.typ {
+            Some(typ) => write!(f, "{}: {}", self.name, typ),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for PrefixOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrefixOp::Neg => write!(f, "-"),
+            PrefixOp::Not => write!(f, "!"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinaryOp::Add => write!(f, "+"),
+            BinaryOp::Sub => write!(f, "-"),
+            BinaryOp::Mul => write!(f, "*"),
+            BinaryOp::Div => write!(f, "/"),
+            BinaryOp::Rem => write!(f, "%"),
+            BinaryOp::Eq => write!(f, "=="),
+            BinaryOp::NotEq => write!(f, "!="),
+            BinaryOp::Lt => write!(f, "<"),
+            BinaryOp::Gt => write!(f, ">"),
+            BinaryOp::Lte => write!(f, "<="),
+            BinaryOp::Gte => write!(f, ">="),
+            BinaryOp::And => write!(f, "&&"),
+            BinaryOp::Or => write!(f, "||"),
+            BinaryOp::BitAnd => write!(f, "&"),
+            BinaryOp::BitOr => write!(f, "|"),
+            BinaryOp::BitXor => write!(f, "^"),
+            BinaryOp::Shl => write!(f, "<<"),
+            BinaryOp::Shr => write!(f, ">>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parse_expr;
+
+    #[test]
+    fn test_display_expr() {
+        let tests = [
+            ("(1 + 2) * 3", "((1 + 2) * 3)"),
+            ("1 + 2 * 3", "(1 + (2 * 3))"),
+            ("a - b - c", "((a - b) - c)"),
+            ("-1 + 2", "((-1) + 2)"),
+            ("!-a", "(!(-a))"),
+            ("a || b && c == 2.5", "(a || (b && (c == 2.5)))"),
+            ("x << 1 | \"hi\" != y", "(((x << 1) | \"hi\") != y)"),
+            ("true", "true"),
+            ("f(1 + 2, g(x))()", "f((1 + 2), g(x))()"),
+            ("-m[i + 1][j]", "(-m[(i + 1)][j])"),
+            ("a.b.push(c.d) + 1.5", "(a.b.push(c.d) + 1.5)"),
+            ("{}", "{}"),
+            (
+                "{ const a: int = 1; f(a); -a }",
+                "{ const a: int = 1; f(a); (-a) }",
+            ),
+            (
+                "if a > 1 { a } else if a { -a } else {}",
+                "if (a > 1) { a } else if a { (-a) } else {}",
+            ),
+            (
+                "{ while !done { x = x + 1; if x > 2 { break }; continue } }",
+                "{ while (!done) { x = (x + 1); if (x > 2) { break; }; continue; }; }",
+            ),
+            (
+                "{ fn f(a: int, b) -> int { a + b } fn g() {} }",
+                "{ fn f(a: int, b) -> int { (a + b) }; fn g() {}; }",
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(parse_expr(input).unwrap().to_string(), *expected);
+        });
+    }
+}
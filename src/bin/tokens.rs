@@ -0,0 +1,20 @@
+use std::{env, fs, process};
+
+/// Prints the tokens of the file given as argument as a JSON array.
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: isy-tokens <file>");
+            process::exit(2);
+        }
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(src) => println!("{}", isy::lexer::tokens_to_json(&src)),
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::lexer::is_new_line;
+use crate::token::Span;
+
+/// Renders `msg` like rustc does, showing the source line where `span` starts
+/// with carets under the spanned text:
+///
+/// ```text
+/// error: invalid number literal '0b2'
+///  --> 2:6
+///   |
+/// 2 | a := 0b2
+///   |      ^^^
+/// ```
+///
+/// Spans covering several lines are underlined up to the end of their first line.
+/// Lines are split like the lexer does, at `\n`, `\r\n` or a lone `\r`.
+pub fn render_diagnostic(src: &str, span: &Span, msg: &str) -> String {
+    let start = span.start.min(src.len());
+    let line_start = src[..start].rfind(is_new_line).map_or(0, |idx| idx + 1);
+    let line_end = src[start..]
+        .find(is_new_line)
+        .map_or(src.len(), |idx| start + idx);
+    // the `\r` of a `\r\n` is not counted
+    let line = src[..start]
+        .char_indices()
+        .filter(|&(idx, ch)| is_new_line(ch) && !src[idx..].starts_with("\r\n"))
+        .count()
+        + 1;
+
+    let text = &src[line_start..line_end];
+    let column = src[line_start..start].chars().count() + 1;
+    let end = span.end.clamp(start, line_start + text.len());
+    let width = src[start..end].chars().count().max(1);
+
+    let gutter = " ".repeat(line.to_string().len());
+    format!(
+        "error: {msg}\n{gutter}--> {line}:{column}\n{gutter} |\n{line} | {text}\n{gutter} | {pad}{carets}",
+        msg = msg,
+        gutter = gutter,
+        line = line,
+        column = column,
+        text = text,
+        pad = " ".repeat(column - 1),
+        carets = "^".repeat(width),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    #[test]
+    fn test_render_diagnostic() {
+        let src = "x := 1\na := 0b2 + 1\n";
+        let err = tokenize(src).unwrap_err();
+        let expected = [
+            "error: invalid number literal '0b2'",
+            " --> 2:6",
+            "  |",
+            "2 | a := 0b2 + 1",
+            "  |      ^^^",
+        ]
+        .join("\n");
+
+        assert_eq!(
+            render_diagnostic(src, &err.span(), &err.to_string()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_render_diagnostic_line_breaks() {
+        let expected = [
+            "error: invalid number literal '0b2'",
+            " --> 3:6",
+            "  |",
+            "3 | a := 0b2 + 1",
+            "  |      ^^^",
+        ]
+        .join("\n");
+
+        [
+            "x := 1\ry := 2\ra := 0b2 + 1\r",
+            "x := 1\r\ny := 2\ra := 0b2 + 1\r\n",
+        ]
+        .iter()
+        .for_each(|src| {
+            let err = tokenize(src).unwrap_err();
+            assert_eq!(
+                render_diagnostic(src, &err.span(), &err.to_string()),
+                expected,
+                "{:?}",
+                src
+            );
+        });
+    }
+
+    #[test]
+    fn test_render_diagnostic_edges() {
+        // empty spans get a single caret, multi-line spans stop at the end of the line
+        let src = "a /* b\n c";
+        let expected = [
+            "error: unterminated comment",
+            " --> 1:3",
+            "  |",
+            "1 | a /* b",
+            "  |   ^^^^",
+        ]
+        .join("\n");
+        assert_eq!(
+            render_diagnostic(src, &Span::new(2, src.len()), "unterminated comment"),
+            expected
+        );
+
+        let src = "é\n\n\n\n\n\n\n\n\nfoo(";
+        let expected = [
+            "error: unexpected EOF",
+            "  --> 10:5",
+            "   |",
+            "10 | foo(",
+            "   |     ^",
+        ]
+        .join("\n");
+        assert_eq!(
+            render_diagnostic(src, &Span::new(src.len(), src.len()), "unexpected EOF"),
+            expected
+        );
+    }
+}
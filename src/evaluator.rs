@@ -0,0 +1,807 @@
+use crate::ast::{BinaryOp, Block, Expr, Param, PrefixOp, Stmt};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// the value of a block without a final expression
+    Null,
+    Function(Rc<Function>),
+}
+
+/// A function declared with `fn`, along with the environment it was declared in.
+///
+/// The environment is a snapshot, so the function sees the variables declared before it
+/// with the values they had at that point. Functions are only equal to themselves.
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Block,
+    pub env: Environment,
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Function) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Value {
+    /// Returns the name of the type of this value as it's spelled in the source.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "string",
+            Value::Null => "null",
+            Value::Function(_) => "fn",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Null => write!(f, "null"),
+            Value::Function(function) => write!(f, "fn {}", function.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    TypeMismatch {
+        op: BinaryOp,
+        left: Value,
+        right: Value,
+    },
+    InvalidOperand {
+        op: PrefixOp,
+        value: Value,
+    },
+    UndefinedVariable(String),
+    /// integer `/` or `%` with a zero divisor, floats follow IEEE and yield `inf` or `NaN` instead.
+    /// The AST doesn't track spans yet so only the operator is reported.
+    DivisionByZero {
+        op: BinaryOp,
+    },
+    NotCallable(Value),
+    NotIndexable(Value),
+    UnknownField {
+        value: Value,
+        field: String,
+    },
+    /// the condition of an `if` or a `while` is not a bool
+    InvalidCondition(Value),
+    /// `break` unwinds as an error until the enclosing loop catches it,
+    /// so it only reaches the caller when used outside of a loop
+    BreakOutsideLoop,
+    /// like `BreakOutsideLoop` but for `continue`
+    ContinueOutsideLoop,
+    /// `break` outside of a loop in the body of a function, reported by the call
+    /// so it's never caught by a loop of the caller
+    BreakInFunction {
+        name: String,
+    },
+    /// like `BreakInFunction` but for `continue`
+    ContinueInFunction {
+        name: String,
+    },
+    /// a function called with the wrong number of arguments
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::TypeMismatch { op, left, right } => write!(
+                f,
+                "unsupported operand types for `{}`: {} and {}",
+                op,
+                left.type_name(),
+                right.type_name()
+            ),
+            EvalError::InvalidOperand { op, value } => write!(
+                f,
+                "unsupported operand type for `{}`: {}",
+                op,
+                value.type_name()
+            ),
+            EvalError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+            EvalError::DivisionByZero { op } => write!(f, "division by zero in `{}`", op),
+            EvalError::NotCallable(value) => {
+                write!(f, "values of type {} are not callable", value.type_name())
+            }
+            EvalError::NotIndexable(value) => {
+                write!(f, "values of type {} can't be indexed", value.type_name())
+            }
+            EvalError::UnknownField { value, field } => {
+                write!(f, "{} has no field `{}`", value.type_name(), field)
+            }
+            EvalError::InvalidCondition(value) => {
+                write!(
+                    f,
+                    "expected a bool condition but found {}",
+                    value.type_name()
+                )
+            }
+            EvalError::BreakOutsideLoop => write!(f, "`break` outside of a loop"),
+            EvalError::ContinueOutsideLoop => write!(f, "`continue` outside of a loop"),
+            EvalError::BreakInFunction { name } => {
+                write!(f, "`break` outside of a loop in `{}`", name)
+            }
+            EvalError::ContinueInFunction { name } => {
+                write!(f, "`continue` outside of a loop in `{}`", name)
+            }
+            EvalError::ArityMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} arguments but {} were given",
+                name, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Stack of scopes binding names to values, lookups go from the innermost scope outwards.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
+
+impl Environment {
+    /// Creates an environment with an empty global scope.
+    pub fn new() -> Environment {
+        Environment {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Drops the innermost scope and its bindings, the global scope is never dropped.
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    /// Binds `name` in the innermost scope, shadowing any previous binding.
+    pub fn declare(&mut self, name: &str, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), value);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+}
+
+/// Runs `stmts` in order against `env`,
+/// returning the value of the last statement if it's an expression.
+pub fn exec(stmts: &[Stmt], env: &mut Environment) -> Result<Option<Value>, EvalError> {
+    let mut last = None;
+    for stmt in stmts {
+        last = match stmt {
+            Stmt::Decl { name, value, .. } => {
+                let value = eval_with(value, env)?;
+                env.declare(name, value);
+                None
+            }
+            Stmt::Assign { name, value } => {
+                let value = eval_with(value, env)?;
+                let var = env
+                    .get_mut(name)
+                    .ok_or_else(|| EvalError::UndefinedVariable(name.clone()))?;
+                *var = value;
+                None
+            }
+            Stmt::While { cond, body } => {
+                exec_while(cond, body, env)?;
+                None
+            }
+            Stmt::FnDecl {
+                name, params, body, ..
+            } => {
+                let function = Function {
+                    name: name.clone(),
+                    params: params.clone(),
+                    body: body.clone(),
+                    env: env.clone(),
+                };
+
+                env.declare(name, Value::Function(Rc::new(function)));
+                None
+            }
+            Stmt::Break => return Err(EvalError::BreakOutsideLoop),
+            Stmt::Continue => return Err(EvalError::ContinueOutsideLoop),
+            Stmt::Expr(expr) => Some(eval_with(expr, env)?),
+        };
+    }
+
+    Ok(last)
+}
+
+fn exec_while(cond: &Expr, body: &Block, env: &mut Environment) -> Result<(), EvalError> {
+    loop {
+        match eval_with(cond, env)? {
+            Value::Bool(true) => {}
+            Value::Bool(false) => return Ok(()),
+            value => return Err(EvalError::InvalidCondition(value)),
+        }
+
+        match eval_block(body, env) {
+            Ok(_) | Err(EvalError::ContinueOutsideLoop) => {}
+            Err(EvalError::BreakOutsideLoop) => return Ok(()),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Evaluates `expr` down to a single value.
+pub fn eval(expr: &Expr) -> Result<Value, EvalError> {
+    eval_with(expr, &mut Environment::new())
+}
+
+/// Evaluates `expr` looking up identifiers in `env`.
+pub fn eval_with(expr: &Expr, env: &mut Environment) -> Result<Value, EvalError> {
+    match expr {
+        Expr::Int(value) => Ok(Value::Int(*value)),
+        Expr::Float(value) => Ok(Value::Float(*value)),
+        Expr::Bool(value) => Ok(Value::Bool(*value)),
+        Expr::Str(value) => Ok(Value::String(value.clone())),
+        Expr::Ident(name) => env
+            .get(name)
+            .cloned()
+            .ok_or_else(|| EvalError::UndefinedVariable(name.clone())),
+        Expr::Prefix { op, right } => eval_prefix(*op, eval_with(right, env)?),
+        Expr::Binary { op, left, right } => eval_binary(*op, left, right, env),
+        Expr::Call { callee, args } => match eval_with(callee, env)? {
+            Value::Function(function) => {
+                let args = args
+                    .iter()
+                    .map(|arg| eval_with(arg, env))
+                    .collect::<Result<Vec<_>, _>>()?;
+                call(&function, args)
+            }
+            value => Err(EvalError::NotCallable(value)),
+        },
+        // there are no collections to index
+        Expr::Index { target, .. } => Err(EvalError::NotIndexable(eval_with(target, env)?)),
+        // or values with fields
+        Expr::Member { object, field } => Err(EvalError::UnknownField {
+            value: eval_with(object, env)?,
+            field: field.clone(),
+        }),
+        Expr::Block(block) => eval_block(block, env),
+        // without an `else` the value is always `null`, like a block without a final expression
+        Expr::If { cond, then, else_ } => match (eval_with(cond, env)?, else_) {
+            (Value::Bool(true), Some(_)) => eval_block(then, env),
+            (Value::Bool(true), None) => eval_block(then, env).map(|_| Value::Null),
+            (Value::Bool(false), Some(else_)) => eval_with(else_, env),
+            (Value::Bool(false), None) => Ok(Value::Null),
+            (value, _) => Err(EvalError::InvalidCondition(value)),
+        },
+    }
+}
+
+// runs the body in the environment of the function, where the function
+// itself is declared too so it can call itself
+fn call(function: &Rc<Function>, args: Vec<Value>) -> Result<Value, EvalError> {
+    if args.len() != function.params.len() {
+        return Err(EvalError::ArityMismatch {
+            name: function.name.clone(),
+            expected: function.params.len(),
+            found: args.len(),
+        });
+    }
+
+    let mut env = function.env.clone();
+    env.push_scope();
+    env.declare(&function.name, Value::Function(Rc::clone(function)));
+    function
+        .params
+        .iter()
+        .zip(args)
+        .for_each(|(param, arg)| env.declare(&param.name, arg));
+
+    eval_block(&function.body, &mut env).map_err(|err| match err {
+        EvalError::BreakOutsideLoop => EvalError::BreakInFunction {
+            name: function.name.clone(),
+        },
+        EvalError::ContinueOutsideLoop => EvalError::ContinueInFunction {
+            name: function.name.clone(),
+        },
+        err => err,
+    })
+}
+
+// runs the block in its own scope, which is dropped even if it fails
+fn eval_block(block: &Block, env: &mut Environment) -> Result<Value, EvalError> {
+    env.push_scope();
+    let value = exec(&block.stmts, env).and_then(|_| match &block.value {
+        Some(value) => eval_with(value, env),
+        None => Ok(Value::Null),
+    });
+    env.pop_scope();
+    value
+}
+
+fn eval_prefix(op: PrefixOp, value: Value) -> Result<Value, EvalError> {
+    match (op, value) {
+        (PrefixOp::Neg, Value::Int(v)) => Ok(Value::Int(v.wrapping_neg())),
+        (PrefixOp::Neg, Value::Float(v)) => Ok(Value::Float(-v)),
+        (PrefixOp::Not, Value::Bool(v)) => Ok(Value::Bool(!v)),
+        (op, value) => Err(EvalError::InvalidOperand { op, value }),
+    }
+}
+
+fn eval_binary(
+    op: BinaryOp,
+    left: &Expr,
+    right: &Expr,
+    env: &mut Environment,
+) -> Result<Value, EvalError> {
+    let left = eval_with(left, env)?;
+
+    // logical operators short-circuit, so the right side is only evaluated when needed
+    match (op, &left) {
+        (BinaryOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+        (BinaryOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+        _ => {}
+    }
+
+    let right = eval_with(right, env)?;
+    if matches!(op, BinaryOp::Div | BinaryOp::Rem) && right == Value::Int(0) {
+        if let Value::Int(_) = left {
+            return Err(EvalError::DivisionByZero { op });
+        }
+    }
+
+    match (&left, &right) {
+        (Value::Int(l), Value::Int(r)) => eval_int(op, *l, *r),
+        (Value::Float(l), Value::Float(r)) => eval_float(op, *l, *r),
+        // mixing ints and floats promotes the int
+        (Value::Int(l), Value::Float(r)) => eval_float(op, *l as f64, *r),
+        (Value::Float(l), Value::Int(r)) => eval_float(op, *l, *r as f64),
+        (Value::Bool(l), Value::Bool(r)) => eval_bool(op, *l, *r),
+        (Value::String(l), Value::String(r)) => eval_string(op, l, r),
+        _ => None,
+    }
+    .ok_or(EvalError::TypeMismatch { op, left, right })
+}
+
+fn eval_int(op: BinaryOp, l: i64, r: i64) -> Option<Value> {
+    let value = match op {
+        BinaryOp::Add => Value::Int(l.wrapping_add(r)),
+        BinaryOp::Sub => Value::Int(l.wrapping_sub(r)),
+        BinaryOp::Mul => Value::Int(l.wrapping_mul(r)),
+        BinaryOp::Div => Value::Int(l.wrapping_div(r)),
+        BinaryOp::Rem => Value::Int(l.wrapping_rem(r)),
+        BinaryOp::BitAnd => Value::Int(l & r),
+        BinaryOp::BitOr => Value::Int(l | r),
+        BinaryOp::BitXor => Value::Int(l ^ r),
+        BinaryOp::Shl => Value::Int(l.wrapping_shl(r as u32)),
+        BinaryOp::Shr => Value::Int(l.wrapping_shr(r as u32)),
+        _ => return compare(op, &l, &r),
+    };
+
+    Some(value)
+}
+
+fn eval_float(op: BinaryOp, l: f64, r: f64) -> Option<Value> {
+    let value = match op {
+        BinaryOp::Add => Value::Float(l + r),
+        BinaryOp::Sub => Value::Float(l - r),
+        BinaryOp::Mul => Value::Float(l * r),
+        BinaryOp::Div => Value::Float(l / r),
+        BinaryOp::Rem => Value::Float(l % r),
+        _ => return compare(op, &l, &r),
+    };
+
+    Some(value)
+}
+
+fn eval_bool(op: BinaryOp, l: bool, r: bool) -> Option<Value> {
+    let value = match op {
+        BinaryOp::And => l && r,
+        BinaryOp::Or => l || r,
+        BinaryOp::Eq => l == r,
+        BinaryOp::NotEq => l != r,
+        _ => return None,
+    };
+
+    Some(Value::Bool(value))
+}
+
+fn eval_string(op: BinaryOp, l: &str, r: &str) -> Option<Value> {
+    match op {
+        BinaryOp::Add => Some(Value::String(format!("{}{}", l, r))),
+        _ => compare(op, l, r),
+    }
+}
+
+fn compare<T: PartialOrd + ?Sized>(op: BinaryOp, l: &T, r: &T) -> Option<Value> {
+    let value = match op {
+        BinaryOp::Eq => l == r,
+        BinaryOp::NotEq => l != r,
+        BinaryOp::Lt => l < r,
+        BinaryOp::Gt => l > r,
+        BinaryOp::Lte => l <= r,
+        BinaryOp::Gte => l >= r,
+        _ => return None,
+    };
+
+    Some(Value::Bool(value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{parse_expr, parse_program};
+
+    fn eval_str(input: &str) -> Result<Value, EvalError> {
+        eval(&parse_expr(input).unwrap())
+    }
+
+    #[test]
+    fn test_eval_arithmetic() {
+        let tests = [
+            ("1 + 2 * 3", Value::Int(7)),
+            ("(1 + 2) * 3", Value::Int(9)),
+            ("-7 % 3", Value::Int(-1)),
+            ("1 << 4 | 1", Value::Int(17)),
+            ("1 + 0.5", Value::Float(1.5)),
+            ("\"foo\" + \"bar\"", Value::String("foobar".to_string())),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(eval_str(input).unwrap(), *expected, "{}", input);
+        });
+    }
+
+    #[test]
+    fn test_eval_comparison_and_logic() {
+        let tests = [
+            ("3 < 4", true),
+            ("2 + 3 * 4 == 14", true),
+            ("1 >= 1.5", false),
+            ("\"a\" < \"b\"", true),
+            ("!(1 != 1)", true),
+            ("true && 1 > 2", false),
+            ("false || !false", true),
+            // the right side is never evaluated
+            ("false && missing", false),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(
+                eval_str(input).unwrap(),
+                Value::Bool(*expected),
+                "{}",
+                input
+            );
+        });
+    }
+
+    #[test]
+    fn test_eval_type_mismatch() {
+        let err = eval_str("1 + \"one\"").unwrap_err();
+        assert_eq!(
+            err,
+            EvalError::TypeMismatch {
+                op: BinaryOp::Add,
+                left: Value::Int(1),
+                right: Value::String("one".to_string()),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "unsupported operand types for `+`: int and string"
+        );
+
+        let err = eval_str("-true").unwrap_err();
+        assert_eq!(err.to_string(), "unsupported operand type for `-`: bool");
+
+        assert!(matches!(
+            eval_str("1 && true"),
+            Err(EvalError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_eval_int_division_by_zero() {
+        let err = eval_str("10 / 0").unwrap_err();
+        assert_eq!(err, EvalError::DivisionByZero { op: BinaryOp::Div });
+        assert_eq!(err.to_string(), "division by zero in `/`");
+
+        let err = eval_str("10 % (2 - 2)").unwrap_err();
+        assert_eq!(err, EvalError::DivisionByZero { op: BinaryOp::Rem });
+    }
+
+    #[test]
+    fn test_eval_float_division_by_zero() {
+        assert_eq!(eval_str("1.0 / 0").unwrap(), Value::Float(f64::INFINITY));
+        assert_eq!(
+            eval_str("-1 / 0.0").unwrap(),
+            Value::Float(f64::NEG_INFINITY)
+        );
+
+        match eval_str("1.0 % 0").unwrap() {
+            Value::Float(value) => assert!(value.is_nan()),
+            value => panic!("expected a float, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn test_eval_not_callable() {
+        let err = eval_str("(1 + 2)(3)").unwrap_err();
+        assert_eq!(err, EvalError::NotCallable(Value::Int(3)));
+        assert_eq!(err.to_string(), "values of type int are not callable");
+
+        let err = eval_str("f(1)").unwrap_err();
+        assert_eq!(err, EvalError::UndefinedVariable("f".to_string()));
+
+        let err = eval_str("true[0]").unwrap_err();
+        assert_eq!(err, EvalError::NotIndexable(Value::Bool(true)));
+        assert_eq!(err.to_string(), "values of type bool can't be indexed");
+
+        let err = eval_str("\"hi\".len()").unwrap_err();
+        assert_eq!(
+            err,
+            EvalError::UnknownField {
+                value: Value::String("hi".to_string()),
+                field: "len".to_string(),
+            }
+        );
+        assert_eq!(err.to_string(), "string has no field `len`");
+    }
+
+    #[test]
+    fn test_exec_declarations() {
+        let mut env = Environment::new();
+        let stmts = parse_program("x := 5; x + 1").unwrap();
+        assert_eq!(exec(&stmts, &mut env).unwrap(), Some(Value::Int(6)));
+        assert_eq!(env.get("x"), Some(&Value::Int(5)));
+
+        let stmts = parse_program("y: float = x * 0.5").unwrap();
+        assert_eq!(exec(&stmts, &mut env).unwrap(), None);
+        assert_eq!(env.get("y"), Some(&Value::Float(2.5)));
+    }
+
+    #[test]
+    fn test_exec_undefined_variable() {
+        let stmts = parse_program("x := 5; y + 1").unwrap();
+        let err = exec(&stmts, &mut Environment::new()).unwrap_err();
+        assert_eq!(err, EvalError::UndefinedVariable("y".to_string()));
+        assert_eq!(err.to_string(), "undefined variable `y`");
+    }
+
+    #[test]
+    fn test_exec_block() {
+        let mut env = Environment::new();
+        let stmts = parse_program("a := 10; b := { a := 1; b := 2; a + b }; b * a").unwrap();
+        assert_eq!(exec(&stmts, &mut env).unwrap(), Some(Value::Int(30)));
+        assert_eq!(env.get("a"), Some(&Value::Int(10)));
+
+        let stmts = parse_program("{}; { x := 1; }").unwrap();
+        assert_eq!(exec(&stmts, &mut env).unwrap(), Some(Value::Null));
+        assert_eq!(env.get("x"), None);
+
+        // the scope of the block is dropped even when it fails
+        let stmts = parse_program("{ y := 1; y / 0 }").unwrap();
+        assert!(exec(&stmts, &mut env).is_err());
+        assert_eq!(env.get("y"), None);
+    }
+
+    #[test]
+    fn test_eval_if() {
+        let tests = [
+            ("if 1 < 2 { 1 } else { 2 }", Value::Int(1)),
+            ("if 1 > 2 { 1 } else { 2 }", Value::Int(2)),
+            (
+                "if false { 1 } else if true { 2 } else { 3 }",
+                Value::Int(2),
+            ),
+            ("if true { 1 }", Value::Null),
+            ("if false { 1 }", Value::Null),
+            // the branch not taken is never evaluated
+            ("if true { 1 } else { missing }", Value::Int(1)),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(eval_str(input).unwrap(), *expected, "{}", input);
+        });
+
+        let err = eval_str("if 1 { 2 }").unwrap_err();
+        assert_eq!(err, EvalError::InvalidCondition(Value::Int(1)));
+        assert_eq!(err.to_string(), "expected a bool condition but found int");
+    }
+
+    #[test]
+    fn test_exec_while() {
+        let input = r#"
+            i := 0
+            while i < 5 {
+                i = i + 1
+            }
+            i
+        "#;
+        let stmts = parse_program(input).unwrap();
+        assert_eq!(
+            exec(&stmts, &mut Environment::new()).unwrap(),
+            Some(Value::Int(5))
+        );
+
+        // sums the odd numbers up to 7
+        let input = r#"
+            i := 0
+            sum := 0
+            while true {
+                i = i + 1
+                if i % 2 == 0 { continue }
+                if i > 7 { break }
+                sum = sum + i
+            }
+            sum
+        "#;
+        let stmts = parse_program(input).unwrap();
+        assert_eq!(
+            exec(&stmts, &mut Environment::new()).unwrap(),
+            Some(Value::Int(16))
+        );
+    }
+
+    #[test]
+    fn test_exec_while_errors() {
+        let tests = [
+            ("while 1 { }", EvalError::InvalidCondition(Value::Int(1))),
+            ("x = 1", EvalError::UndefinedVariable("x".to_string())),
+            ("{ break }", EvalError::BreakOutsideLoop),
+            ("if true { continue }", EvalError::ContinueOutsideLoop),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            let stmts = parse_program(input).unwrap();
+            assert_eq!(
+                exec(&stmts, &mut Environment::new()).unwrap_err(),
+                *expected,
+                "{}",
+                input
+            );
+        });
+
+        assert_eq!(
+            EvalError::BreakOutsideLoop.to_string(),
+            "`break` outside of a loop"
+        );
+    }
+
+    #[test]
+    fn test_exec_functions() {
+        let input = r#"
+            factor := 3
+            fn scale(x: int) -> int { x * factor }
+            fn apply(f, x) { f(x) }
+            fn fact(n: int) -> int {
+                if n < 2 { 1 } else { n * fact(n - 1) }
+            }
+
+            // functions see the outer variables as they were when declared
+            factor = 10
+            scale(2) + apply(scale, 5) + fact(5)
+        "#;
+        let mut env = Environment::new();
+        let stmts = parse_program(input).unwrap();
+        assert_eq!(exec(&stmts, &mut env).unwrap(), Some(Value::Int(141)));
+
+        let scale = env.get("scale").unwrap().clone();
+        assert_eq!(scale.to_string(), "fn scale");
+        assert_eq!(scale, scale.clone());
+        assert_ne!(Some(&scale), env.get("apply"));
+    }
+
+    #[test]
+    fn test_exec_function_loop_control() {
+        let tests = [
+            (
+                "fn f() { break }; while true { f() }",
+                EvalError::BreakInFunction {
+                    name: "f".to_string(),
+                },
+            ),
+            (
+                "fn g(x) { if x { continue } }; i := 0; while i < 3 { i = i + 1; g(true) }",
+                EvalError::ContinueInFunction {
+                    name: "g".to_string(),
+                },
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            let stmts = parse_program(input).unwrap();
+            assert_eq!(
+                exec(&stmts, &mut Environment::new()).unwrap_err(),
+                *expected,
+                "{}",
+                input
+            );
+        });
+
+        // loops inside the function still catch them
+        let input = "fn f() { i := 0; while true { i = i + 1; if i > 2 { break } }; i }; f()";
+        let stmts = parse_program(input).unwrap();
+        assert_eq!(
+            exec(&stmts, &mut Environment::new()).unwrap(),
+            Some(Value::Int(3))
+        );
+
+        assert_eq!(tests[0].1.to_string(), "`break` outside of a loop in `f`");
+    }
+
+    #[test]
+    fn test_exec_function_arity() {
+        let stmts = parse_program("fn add(a, b) { a + b }; add(1)").unwrap();
+        let err = exec(&stmts, &mut Environment::new()).unwrap_err();
+        assert_eq!(
+            err,
+            EvalError::ArityMismatch {
+                name: "add".to_string(),
+                expected: 2,
+                found: 1,
+            }
+        );
+        assert_eq!(err.to_string(), "`add` takes 2 arguments but 1 were given");
+    }
+
+    #[test]
+    fn test_environment_scopes() {
+        let mut env = Environment::new();
+        env.declare("x", Value::Int(1));
+
+        env.push_scope();
+        env.declare("x", Value::Bool(true));
+        env.declare("y", Value::Int(2));
+        assert_eq!(env.get("x"), Some(&Value::Bool(true)));
+        env.pop_scope();
+
+        assert_eq!(env.get("x"), Some(&Value::Int(1)));
+        assert_eq!(env.get("y"), None);
+    }
+}
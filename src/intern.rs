@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// Interned string, comparing and hashing two symbols is as cheap as comparing two integers.
+///
+/// Symbols are created with `Symbol::intern` and resolved with `Symbol::as_str`
+/// through a global `Interner`, so they are valid across threads.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Returns the symbol of `text`, reusing the existing one if it was already interned.
+    pub fn intern(text: &str) -> Symbol {
+        with_interner(|interner| interner.intern(text))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        with_interner(|interner| interner.resolve(*self))
+    }
+
+    /// Index of the symbol in the interner.
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Symbol {
+        Symbol::intern(text)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Symbol, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(Symbol::intern(&text))
+    }
+}
+
+/// Pool of unique strings, each one stored once and identified by a `Symbol`.
+///
+/// Interned strings are leaked so they can be handed out as `&'static str`,
+/// the memory used is bounded by the number of distinct strings.
+#[derive(Debug, Default)]
+pub struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(text) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.strings.len() as u32);
+        let text: &'static str = Box::leak(text.to_string().into_boxed_str());
+        self.strings.push(text);
+        self.symbols.insert(text, symbol);
+        symbol
+    }
+
+    /// Returns the string of `symbol`.
+    /// Panics if `symbol` was created by a different interner.
+    pub fn resolve(&self, symbol: Symbol) -> &'static str {
+        self.strings[symbol.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+fn with_interner<T>(f: impl FnOnce(&mut Interner) -> T) -> T {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    let mut interner = INTERNER
+        .get_or_init(|| Mutex::new(Interner::new()))
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    f(&mut interner)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        let a = interner.intern("foo");
+        let b = interner.intern("bar");
+        let c = interner.intern("foo");
+
+        assert_eq!(a, c);
+        assert_ne!(a, b);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(a), "foo");
+        assert_eq!(interner.resolve(b), "bar");
+    }
+
+    #[test]
+    fn test_symbol() {
+        let a = Symbol::intern("my_symbol");
+        assert_eq!(a, Symbol::from("my_symbol"));
+        assert_eq!(a.as_str(), "my_symbol");
+        assert_eq!(a.to_string(), "my_symbol");
+        assert_eq!(format!("{:?}", a), "\"my_symbol\"");
+    }
+}
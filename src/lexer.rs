@@ -1,23 +1,161 @@
-use crate::token;
-use crate::token::{Token, TokenType};
-use std::str::{CharIndices, Chars};
+use crate::intern::Symbol;
+use crate::token::{NumberSuffix, Span, Token, TokenKind, TokenType};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::num::IntErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    InvalidNumber { literal: String, span: Span },
+    NumberOverflow { literal: String, span: Span },
+    UnterminatedComment { span: Span },
+    UnterminatedString { span: Span },
+    InvalidEscape { sequence: String, span: Span },
+    InvalidChar { span: Span },
+    LimitExceeded { limit: Limit, span: Span },
+    InvalidByte { ch: char, span: Span },
+}
+
+/// Limit set in `LexerOptions` that an input went over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    /// maximum length in bytes of a single token
+    TokenLength(usize),
+    /// maximum length in bytes of the whole input
+    InputSize(usize),
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::InvalidNumber { literal, .. } => {
+                write!(f, "invalid number literal '{}'", literal)
+            }
+            LexError::NumberOverflow { literal, .. } => {
+                write!(f, "number literal '{}' is too large", literal)
+            }
+            LexError::UnterminatedComment { .. } => write!(f, "unterminated block comment"),
+            LexError::UnterminatedString { .. } => write!(f, "unterminated string literal"),
+            LexError::InvalidEscape { sequence, .. } => {
+                write!(f, "invalid escape sequence '{}'", sequence)
+            }
+            LexError::InvalidChar { .. } => {
+                write!(f, "char literals must contain exactly one character")
+            }
+            LexError::LimitExceeded { limit, .. } => match limit {
+                Limit::TokenLength(max) => write!(f, "token is longer than {} bytes", max),
+                Limit::InputSize(max) => write!(f, "input is larger than {} bytes", max),
+            },
+            LexError::InvalidByte { ch, .. } => {
+                write!(f, "'{}' is not a byte, use a \\u escape to encode it", ch)
+            }
+        }
+    }
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::InvalidNumber { span, .. }
+            | LexError::NumberOverflow { span, .. }
+            | LexError::UnterminatedComment { span }
+            | LexError::UnterminatedString { span }
+            | LexError::InvalidEscape { span, .. }
+            | LexError::InvalidChar { span }
+            | LexError::LimitExceeded { span, .. }
+            | LexError::InvalidByte { span, .. } => *span,
+        }
+    }
+
+    fn span_mut(&mut self) -> &mut Span {
+        match self {
+            LexError::InvalidNumber { span, .. }
+            | LexError::NumberOverflow { span, .. }
+            | LexError::UnterminatedComment { span }
+            | LexError::UnterminatedString { span }
+            | LexError::InvalidEscape { span, .. }
+            | LexError::InvalidChar { span }
+            | LexError::LimitExceeded { span, .. }
+            | LexError::InvalidByte { span, .. } => span,
+        }
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// Switches for the optional behaviors of the lexer,
+/// `LexerOptions::default()` lexes like `Lexer::new`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexerOptions {
+    /// emit comments as `Comment` tokens instead of skipping them
+    pub keep_comments: bool,
+    /// emit whitespace as `Whitespace` tokens and comments as `Comment` tokens,
+    /// so concatenating the lexemes of every token reproduces the input
+    pub lossless: bool,
+    /// treat `#` up to the end of the line as a comment, like `//`
+    pub hash_comments: bool,
+    /// reject tokens longer than this many bytes with `LexError::LimitExceeded`
+    pub max_token_len: Option<usize>,
+    /// reject inputs larger than this many bytes with `LexError::LimitExceeded`,
+    /// nothing is lexed from them and the next token is `EOF`
+    pub max_input_len: Option<usize>,
+    /// lex a `-` right before a number as part of it, like `-5` as `Int(-5)`, where
+    /// an operand is expected: at the start of the input or after an operator, `(`,
+    /// `[` or `,`. Anywhere else it's still a subtraction, so `a -5` and `a - 5` are
+    /// `a` minus `5` but `f(-5)` and `1 - -5` get a negative literal. `- 5` is never
+    /// a literal. The sign is part of the literal, so `-.5` is a float and `-128i8`
+    /// or `-9223372036854775808` are in range
+    pub lex_negative_numbers: bool,
+}
 
 pub struct Lexer<'a> {
     input: &'a str,
+    options: LexerOptions,
     chars: CharIndices<'a>,
     pos: usize,
-    read_position: usize,
     ch: Option<char>,
+    prev_ch: Option<char>,
+    line: usize,
+    column: usize,
+    // byte position, line and column where the current token (or comment) starts
+    token_start: (usize, usize, usize),
+    // one entry per string with an open interpolation: where the string starts
+    // and how many braces are open inside the interpolated expression
+    interpolations: Vec<(usize, usize)>,
+    // skip a `#!` line at the start of the input, false when the input is not
+    // the start of a file, like the later lines of a `StreamLexer`
+    shebang: bool,
+    // kind of the last token returned, whitespace and comments aside,
+    // `None` until the first one
+    prev_kind: Option<TokenKind>,
+    finished: bool,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &'a str) -> Lexer {
+    pub fn new(input: &'a str) -> Lexer<'a> {
+        Lexer::with_options(input, LexerOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: LexerOptions) -> Lexer<'a> {
         let mut lexer = Lexer {
             input,
+            options,
             chars: input.char_indices(),
             pos: 0,
-            read_position: 0,
             ch: None,
+            prev_ch: None,
+            line: 1,
+            column: 1,
+            token_start: (0, 1, 1),
+            interpolations: vec![],
+            shebang: true,
+            prev_kind: None,
+            finished: false,
         };
 
         lexer.next_char();
@@ -25,7 +163,22 @@ impl<'a> Lexer<'a> {
         lexer
     }
 
+    /// Starts lexing `input` from the beginning, reusing this lexer.
+    /// Spans and positions of the new tokens are relative to the new input.
+    /// The options are kept.
+    pub fn reset(&mut self, input: &'a str) {
+        *self = Lexer::with_options(input, self.options);
+    }
+
+    /// Line and column where the lexer will continue reading, one past the last
+    /// character of the input once it's exhausted, which is where `EOF` is reported.
+    pub fn current_position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
     fn next_char(&mut self) {
+        self.prev_ch = self.ch;
+
         match self.chars.next() {
             Some((pos, ch)) => {
                 self.ch = Some(ch);
@@ -36,276 +189,2808 @@ impl<'a> Lexer<'a> {
                 self.pos = self.input.len();
             }
         }
+
+        match self.prev_char() {
+            // `\r\n` is a single line break, counted on the `\n`
+            Some('\r') if self.ch == Some('\n') => {}
+            Some(ch) if is_new_line(ch) => {
+                self.line += 1;
+                self.column = 1;
+            }
+            Some(_) => self.column += 1,
+            None => {}
+        }
+    }
+
+    // ascii fast path, moves past the bytes matching `f` at once instead of going
+    // through `next_char` for each one. `f` must only match ascii bytes other than
+    // line breaks, so the column is the only position that changes
+    fn skip_ascii_while(&mut self, f: impl Fn(u8) -> bool) {
+        let bytes = &self.input.as_bytes()[self.pos..];
+        let len = bytes.iter().take_while(|byte| f(**byte)).count();
+        if len == 0 {
+            return;
+        }
+
+        self.prev_ch = Some(char::from(bytes[len - 1]));
+        self.column += len;
+        match self.chars.nth(len - 1) {
+            Some((pos, ch)) => {
+                self.ch = Some(ch);
+                self.pos = pos;
+            }
+            None => {
+                self.ch = None;
+                self.pos = self.input.len();
+            }
+        }
     }
 
-    fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+    /// Returns the next token, or the error found lexing it.
+    /// Once the input is exhausted it keeps returning `EOF`.
+    pub fn next_token(&mut self) -> Result<Token<'a>, LexError> {
+        self.check_input_len()?;
+        let trivia = self.skip_whitespace_and_comments()?;
+
+        let (start, line, column) = self.token_start;
+        if let Some(typ) = trivia {
+            self.check_token_len(start)?;
+            return Ok(Token::new(typ, Span::new(start, self.pos), line, column));
+        }
 
         let mut read_next = true;
         let typ = match self.ch {
+            Some('?') => TokenType::Question,
             Some(':') => TokenType::Colon,
-            Some('+') => TokenType::Plus,
-            Some('-') => TokenType::Minus,
-            Some('/') => TokenType::Slash,
-            Some('*') => TokenType::Asterisk,
-            Some('%') => TokenType::Module,
-            Some('"') => self.read_string().unwrap(),
+            Some(',') => TokenType::Comma,
+            Some(';') => TokenType::Semicolon,
+            Some('@') => TokenType::At,
+            // `.5` is a float unless the dot is accessing a member, like in `a.5` or `(a).0`
+            Some('.') if is_digit(self.peek_char()) && !self.follows_operand() => {
+                read_next = false;
+                self.read_number()?
+            }
+            // dots followed by a digit after a number are read as part of a float
+            Some('.') => match self.peek_char() {
+                Some('.') => {
+                    self.next_char();
+                    match self.peek_char() {
+                        Some('=') => self.consume_peeked(TokenType::DotDotEq),
+                        _ => TokenType::DotDot,
+                    }
+                }
+                _ => TokenType::Dot,
+            },
+            Some('(') => TokenType::LParen,
+            Some(')') => TokenType::RParen,
+            Some('{') => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+
+                TokenType::LBrace
+            }
+            Some('}') => match self.interpolations.last_mut() {
+                Some((string_start, 0)) => {
+                    let string_start = *string_start;
+                    self.interpolations.pop();
+                    self.read_string_content(string_start, false)?
+                }
+                Some((_, depth)) => {
+                    *depth -= 1;
+                    TokenType::RBrace
+                }
+                None => TokenType::RBrace,
+            },
+            Some('[') => TokenType::LBracket,
+            Some(']') => TokenType::RBracket,
+            Some('+') => match self.peek_char() {
+                Some('=') => self.consume_peeked(TokenType::PlusAssign),
+                _ => TokenType::Plus,
+            },
+            Some('-')
+                if self.options.lex_negative_numbers
+                    && self.peek_number()
+                    && self.expects_operand() =>
+            {
+                read_next = false;
+                self.read_negative_number()?
+            }
+            Some('-') => match self.peek_char() {
+                Some('>') => self.consume_peeked(TokenType::Arrow),
+                Some('=') => self.consume_peeked(TokenType::MinusAssign),
+                _ => TokenType::Minus,
+            },
+            Some('/') => match self.peek_char() {
+                Some('=') => self.consume_peeked(TokenType::SlashAssign),
+                _ => TokenType::Slash,
+            },
+            Some('*') => match self.peek_char() {
+                Some('=') => self.consume_peeked(TokenType::AsteriskAssign),
+                Some('*') => self.consume_peeked(TokenType::Power),
+                _ => TokenType::Asterisk,
+            },
+            Some('%') => match self.peek_char() {
+                Some('=') => self.consume_peeked(TokenType::PercentAssign),
+                _ => TokenType::Percent,
+            },
+            Some('"') if self.starts_with("\"\"\"") => self.read_multiline_string()?,
+            Some('"') => self.read_string()?,
+            Some('\'') => self.read_char()?,
+            // operators are matched greedily, the longest one starting here wins and its chars
+            // are all consumed before the next token, so `===` is `==` followed by `=`
+            // and `a==b` lexes the same as `a == b`
             Some('=') => match self.peek_char() {
-                Some('=') => TokenType::Equal,
+                Some('=') => self.consume_peeked(TokenType::Equal),
+                Some('>') => self.consume_peeked(TokenType::FatArrow),
                 _ => TokenType::Assign,
             },
             Some('!') => match self.peek_char() {
-                Some('=') => TokenType::NotEqual,
+                Some('=') => self.consume_peeked(TokenType::NotEqual),
                 _ => TokenType::Bang,
             },
+            Some('<') => match self.peek_char() {
+                Some('=') => self.consume_peeked(TokenType::Lte),
+                Some('<') => self.consume_peeked(TokenType::Shl),
+                _ => TokenType::Lt,
+            },
+            // `>>` is always a shift, a parser dealing with nested generics
+            // needs to split it into two `>` by itself
+            Some('>') => match self.peek_char() {
+                Some('=') => self.consume_peeked(TokenType::Gte),
+                Some('>') => self.consume_peeked(TokenType::Shr),
+                _ => TokenType::Gt,
+            },
+            Some('&') => match self.peek_char() {
+                Some('&') => self.consume_peeked(TokenType::And),
+                _ => TokenType::BitAnd,
+            },
+            Some('|') => match self.peek_char() {
+                Some('|') => self.consume_peeked(TokenType::Or),
+                _ => TokenType::BitOr,
+            },
+            Some('^') => TokenType::BitXor,
+            Some('~') => TokenType::BitNot,
+            Some('b') if self.peek_char() == Some('"') => self.read_byte_string()?,
             Some(ch) => {
-                if is_letter(self.ch) {
+                if let Some(hashes) = self.raw_string_hashes() {
+                    self.read_raw_string(hashes)?
+                } else if is_ident_start(self.ch) {
                     read_next = false;
-                    self.read_identifier().unwrap()
+                    self.read_identifier()?
                 } else if is_digit(self.ch) {
                     read_next = false;
-                    self.read_number().unwrap()
+                    self.read_number()?
                 } else {
                     TokenType::Illegal(ch)
                 }
             }
-            None => TokenType::EOF,
+            None => match self.interpolations.pop() {
+                Some((string_start, _)) => {
+                    let span = Span::new(string_start, self.pos);
+                    return Err(LexError::UnterminatedString { span });
+                }
+                None => TokenType::EOF,
+            },
         };
 
         if read_next {
             self.next_char();
         }
 
-        Token::new(typ)
+        self.check_token_len(start)?;
+        let mut tok = Token::new(typ, Span::new(start, self.pos), line, column);
+        if let TokenType::Int(_)
+        | TokenType::Float(_)
+        | TokenType::IntSuffixed(..)
+        | TokenType::FloatSuffixed(..) = tok.typ
+        {
+            tok.raw = Some(Cow::Borrowed(&self.input[start..self.pos]));
+        }
+
+        self.prev_kind = Some(tok.typ.kind());
+        Ok(tok)
     }
 
-    fn peek_char(&self) -> Option<char> {
-        if self.pos >= self.input.len() {
-            return None;
+    // jumps to the end of an input over the size limit, so it's rejected only once
+    fn check_input_len(&mut self) -> Result<(), LexError> {
+        match self.options.max_input_len {
+            Some(max) if self.input.len() > max && self.ch.is_some() => {
+                let span = Span::new(self.pos, self.input.len());
+                while self.ch.is_some() {
+                    self.next_char();
+                }
+
+                let limit = Limit::InputSize(max);
+                Err(LexError::LimitExceeded { limit, span })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_token_len(&self, start: usize) -> Result<(), LexError> {
+        match self.options.max_token_len {
+            Some(max) if self.pos - start > max => {
+                let limit = Limit::TokenLength(max);
+                let span = Span::new(start, self.pos);
+                Err(LexError::LimitExceeded { limit, span })
+            }
+            _ => Ok(()),
         }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        self.input[self.pos..].starts_with(s)
+    }
+
+    // used by two chars tokens to move past the peeked char
+    fn consume_peeked(&mut self, typ: TokenType<'a>) -> TokenType<'a> {
+        self.next_char();
+        typ
+    }
+
+    // byte offset where the char after the current one starts, any slice ending
+    // here includes the current char whatever its length is
+    fn next_pos(&self) -> usize {
+        self.chars
+            .clone()
+            .next()
+            .map_or(self.input.len(), |(pos, _)| pos)
+    }
 
-        self.input.chars().nth(self.pos + 1)
+    // `chars` is always one char ahead of `ch`, cloning it is cheap
+    fn peek_char(&self) -> Option<char> {
+        self.chars.clone().next().map(|(_, ch)| ch)
+    }
+
+    // true if the current char is glued to the end of an identifier, number or closing delimiter
+    fn follows_operand(&self) -> bool {
+        is_ident_continue(self.prev_char()) || matches!(self.prev_char(), Some(')') | Some(']'))
     }
 
+    // the char consumed right before the current one
     fn prev_char(&self) -> Option<char> {
-        if self.pos == 0 {
-            return None;
-        }
+        self.prev_ch
+    }
 
-        self.input.chars().nth(self.pos - 1)
+    fn read_string(&mut self) -> Result<TokenType<'a>, LexError> {
+        self.read_string_content(self.pos, true)
     }
 
-    fn read_string(&mut self) -> Result<TokenType, String> {
-        let initial_pos = self.pos + 1;
-        let mut escape = false;
-        while self.peek_char().is_some() {
+    // reads from an opening quote, or from the `}` closing an interpolated expression, up to
+    // the closing quote or the `{` that starts the next interpolated expression
+    fn read_string_content(
+        &mut self,
+        start: usize,
+        opening: bool,
+    ) -> Result<TokenType<'a>, LexError> {
+        // the text is borrowed from the input unless an escape needs to be decoded
+        let content_start = self.pos + 1;
+        let mut decoded: Option<String> = None;
+        let mut error = None;
+        loop {
             self.next_char();
 
             match self.ch {
-                Some('\\') if !escape => {
-                    escape = true;
-                    continue;
+                None => {
+                    let span = Span::new(start, self.pos);
+                    return Err(LexError::UnterminatedString { span });
                 }
-                Some('"') => {
-                    if !escape {
-                        break;
+                // line continuation, the backslash and the new line are dropped
+                Some('\\') if self.peek_char().is_some_and(is_new_line) => {
+                    let escape_pos = self.pos;
+                    self.next_char();
+                    if self.starts_with("\r\n") {
+                        self.next_char();
                     }
+                    decoded
+                        .get_or_insert_with(|| self.input[content_start..escape_pos].to_string());
                 }
-                _ => {}
+                Some('\\') if self.peek_char().is_some() => {
+                    let escape_pos = self.pos;
+                    match self.read_escape() {
+                        Ok(ch) => decoded
+                            .get_or_insert_with(|| {
+                                self.input[content_start..escape_pos].to_string()
+                            })
+                            .push(ch),
+                        Err(err) => {
+                            error.get_or_insert(err);
+                        }
+                    }
+                }
+                Some('"') => break,
+                Some('{') => {
+                    self.interpolations.push((start, 0));
+                    break;
+                }
+                Some(ch) => {
+                    if let Some(decoded) = &mut decoded {
+                        decoded.push(ch);
+                    }
+                }
+            }
+        }
+
+        // the whole string is consumed before reporting a bad escape so lexing
+        // can go on after the closing quote
+        if let Some(err) = error {
+            if self.ch == Some('{') {
+                if !self.skip_interpolated_string() {
+                    let span = Span::new(start, self.pos);
+                    return Err(LexError::UnterminatedString { span });
+                }
+            } else {
+                self.next_char();
             }
 
-            escape = false;
+            return Err(err);
         }
 
-        Ok(TokenType::String(
-            self.input[initial_pos..self.pos].to_string(),
-        ))
+        let value = match decoded {
+            Some(decoded) => Cow::Owned(decoded),
+            None => Cow::Borrowed(&self.input[content_start..self.pos]),
+        };
+
+        let closed = self.ch == Some('"');
+        Ok(match (opening, closed) {
+            (true, true) => TokenType::String(value),
+            (true, false) => TokenType::StringStart(value),
+            (false, false) => TokenType::StringMiddle(value),
+            (false, true) => TokenType::StringEnd(value),
+        })
     }
 
-    fn read_number(&mut self) -> Result<TokenType, String> {
-        let mut is_float = false;
-        let initial_pos = self.pos;
-        loop {
-            if is_digit(self.ch) {
-                self.next_char();
-                continue;
+    // skips the rest of a string from the `{` of one of its interpolations up to the closing
+    // quote, lexing the expressions in between so their braces and strings are matched.
+    // Returns false if the input ends before the closing quote
+    fn skip_interpolated_string(&mut self) -> bool {
+        let token_start = self.token_start;
+        let depth = self.interpolations.len();
+        let mut terminated = true;
+        self.next_char();
+        while self.interpolations.len() >= depth {
+            match self.next_token() {
+                Err(LexError::UnterminatedString { .. })
+                | Err(LexError::UnterminatedComment { .. }) => terminated = false,
+                Err(_) if self.interpolations.len() >= depth => self.recover(),
+                _ => {}
             }
+        }
 
-            if !is_float {
-                if let Some('.') = self.ch {
-                    if is_digit(self.peek_char()) {
-                        is_float = true;
+        self.token_start = token_start;
+        terminated
+    }
+
+    // triple quoted strings can contain `"` and `""`, escapes are processed as usual but
+    // braces are not interpolated
+    fn read_multiline_string(&mut self) -> Result<TokenType<'a>, LexError> {
+        let start = self.pos;
+
+        // skip the opening quotes
+        (0..3).for_each(|_| self.next_char());
+
+        let content_start = self.pos;
+        let content_end;
+        let mut decoded: Option<String> = None;
+        let mut error = None;
+        loop {
+            match self.ch {
+                None => {
+                    let span = Span::new(start, self.pos);
+                    return Err(LexError::UnterminatedString { span });
+                }
+                Some('"') if self.starts_with("\"\"\"") => {
+                    content_end = self.pos;
+                    self.next_char();
+                    self.next_char();
+                    break;
+                }
+                // line continuation, the backslash and the new line are dropped
+                Some('\\') if self.peek_char().is_some_and(is_new_line) => {
+                    let escape_pos = self.pos;
+                    self.next_char();
+                    if self.starts_with("\r\n") {
                         self.next_char();
-                        continue;
+                    }
+                    decoded
+                        .get_or_insert_with(|| self.input[content_start..escape_pos].to_string());
+                }
+                Some('\\') if self.peek_char().is_some() => {
+                    let escape_pos = self.pos;
+                    match self.read_escape() {
+                        Ok(ch) => decoded
+                            .get_or_insert_with(|| {
+                                self.input[content_start..escape_pos].to_string()
+                            })
+                            .push(ch),
+                        Err(err) => {
+                            error.get_or_insert(err);
+                        }
+                    }
+                }
+                Some(ch) => {
+                    if let Some(decoded) = &mut decoded {
+                        decoded.push(ch);
                     }
                 }
             }
 
-            break;
+            self.next_char();
         }
 
-        let num = &self.input[initial_pos..self.pos];
-        if is_float {
-            let float_num = num.parse::<f32>().map_err(|e| e.to_string())?;
-            return Ok(TokenType::Float(float_num));
+        if let Some(err) = error {
+            self.next_char();
+            return Err(err);
         }
 
-        let int_num = num.parse::<i32>().map_err(|e| e.to_string())?;
-        Ok(TokenType::Int(int_num))
+        Ok(TokenType::String(match decoded {
+            Some(decoded) => Cow::Owned(decoded),
+            None => Cow::Borrowed(&self.input[content_start..content_end]),
+        }))
     }
 
-    fn read_identifier(&mut self) -> Result<TokenType, String> {
-        let initial_pos = self.pos;
-        while is_letter(self.ch) || is_digit(self.ch) {
-            self.next_char();
+    // returns the number of `#` if the current `r` starts a raw string like `r"..."` or `r#"..."#`
+    fn raw_string_hashes(&self) -> Option<usize> {
+        if self.ch != Some('r') {
+            return None;
         }
 
-        let ident = &self.input[initial_pos..self.pos];
-        Ok(lookup_ident(ident))
+        let mut chars = self.chars.clone().map(|(_, ch)| ch);
+        let mut hashes = 0;
+        loop {
+            match chars.next() {
+                Some('#') => hashes += 1,
+                Some('"') => return Some(hashes),
+                _ => return None,
+            }
+        }
     }
 
-    fn skip_whitespace(&mut self) {
-        while let Some(' ') = self.ch {
-            self.next_char();
+    // raw strings don't process escapes and end at the first `"` followed by the same number
+    // of `#` used to open it
+    fn read_raw_string(&mut self, hashes: usize) -> Result<TokenType<'a>, LexError> {
+        let start = self.pos;
+
+        // skip the `r`, the hashes and the opening quote
+        (0..hashes + 2).for_each(|_| self.next_char());
+
+        let initial_pos = self.pos;
+        loop {
+            match self.ch {
+                None => {
+                    let span = Span::new(start, self.pos);
+                    return Err(LexError::UnterminatedString { span });
+                }
+                Some('"') if self.closes_raw_string(hashes) => {
+                    let value = Cow::Borrowed(&self.input[initial_pos..self.pos]);
+                    (0..hashes).for_each(|_| self.next_char());
+                    return Ok(TokenType::String(value));
+                }
+                _ => self.next_char(),
+            }
         }
     }
-}
 
-fn lookup_ident(ident: &str) -> TokenType {
-    match ident {
-        "true" => TokenType::Bool(true),
-        "false" => TokenType::Bool(false),
-        "bool" | "int" | "float" | "string" => TokenType::Type(ident.to_string()),
-        _ => TokenType::Ident(ident.to_string()),
+    fn closes_raw_string(&self, hashes: usize) -> bool {
+        let mut chars = self.chars.clone();
+        (0..hashes).all(|_| matches!(chars.next(), Some((_, '#'))))
     }
-}
 
-fn is_new_line(ch: char) -> bool {
-    ch == '\n' // \t? \r?
-}
+    // chars up to U+00FF are stored as a single byte, whether they are escaped or not.
+    // Other code points must be written as `\u{...}` escapes and are stored as their UTF-8
+    // encoding
+    fn read_byte_string(&mut self) -> Result<TokenType<'a>, LexError> {
+        let start = self.pos;
 
-fn is_letter(ch: Option<char>) -> bool {
-    match ch {
-        Some('a'..='z') | Some('A'..='Z') | Some('_') => true,
-        _ => false,
-    }
-}
+        // skip the `b`
+        self.next_char();
 
-fn is_digit(ch: Option<char>) -> bool {
-    match ch {
-        Some('0'..='9') => true,
-        _ => false,
-    }
-}
+        let mut bytes = vec![];
+        let mut error = None;
+        loop {
+            self.next_char();
 
-#[cfg(test)]
-mod test {
-    use super::*;
+            let result = match self.ch {
+                None => {
+                    let span = Span::new(start, self.pos);
+                    return Err(LexError::UnterminatedString { span });
+                }
+                Some('"') => break,
+                Some('\\') if self.peek_char() == Some('x') => {
+                    let initial_pos = self.pos;
+                    self.next_char();
+                    self.read_hex_escape(initial_pos)
+                        .map(|byte| bytes.push(byte))
+                }
+                Some('\\') if self.peek_char().is_some() => {
+                    self.read_escape()
+                        .map(|ch| match u8::try_from(u32::from(ch)) {
+                            Ok(byte) => bytes.push(byte),
+                            Err(_) => {
+                                let mut buf = [0; 4];
+                                bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                            }
+                        })
+                }
+                Some(ch) => u8::try_from(u32::from(ch))
+                    .map(|byte| bytes.push(byte))
+                    .map_err(|_| LexError::InvalidByte {
+                        ch,
+                        span: Span::new(self.pos, self.next_pos()),
+                    }),
+            };
 
-    macro_rules! assert_tokens {
-        ($input:expr, $tokens:expr) => {{
-            let mut lexer = Lexer::new($input);
-            $tokens.iter().enumerate().for_each(|(i, t)| {
-                let tok = lexer.next_token();
-                assert_eq!(tok.typ, *t, "Wrong token type at index: {}", i);
-            });
-        }};
-    }
+            if let Err(err) = result {
+                error.get_or_insert(err);
+            }
+        }
 
-    #[test]
-    fn test_next_token_var_int() {
-        let input = "my_var := 10";
-        let tests = vec![
-            TokenType::Ident(String::from("my_var")),
-            TokenType::Colon,
-            TokenType::Assign,
-            TokenType::Int(10),
-            TokenType::EOF,
-        ];
+        // like in strings, errors are reported after the closing quote
+        if let Some(err) = error {
+            self.next_char();
+            return Err(err);
+        }
 
-        assert_tokens!(input, tests);
+        Ok(TokenType::Bytes(bytes))
     }
 
-    #[test]
-    fn test_next_token_var_float() {
-        let input = "my_var3 := 99.0";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var3")),
-            TokenType::Colon,
-            TokenType::Assign,
-            TokenType::Float(99.0),
-            TokenType::EOF,
-        ];
+    fn read_char(&mut self) -> Result<TokenType<'a>, LexError> {
+        let start = self.pos;
+        self.next_char();
 
-        assert_tokens!(input, tokens);
-    }
+        let value = match self.ch {
+            Some('\\') if self.peek_char().is_some() => Some(self.read_escape()?),
+            Some('\'') | None => None,
+            Some(ch) => Some(ch),
+        };
 
-    #[test]
-    fn test_next_token_var_string() {
-        let input = "my_var := \"hello\"";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var")),
-            TokenType::Colon,
-            TokenType::Assign,
-            TokenType::String("hello".to_string()),
-            TokenType::EOF,
-        ];
+        if value.is_some() {
+            self.next_char();
+        }
 
-        assert_tokens!(input, tokens);
+        match (value, self.ch) {
+            (Some(value), Some('\'')) => Ok(TokenType::Char(value)),
+            _ => {
+                let end = self.next_pos();
+                Err(LexError::InvalidChar {
+                    span: Span::new(start, end),
+                })
+            }
+        }
     }
 
-    #[test]
-    fn test_next_token_var_true() {
-        let input = "my_var3 := true";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var3")),
-            TokenType::Colon,
-            TokenType::Assign,
-            TokenType::Bool(true),
-            TokenType::EOF,
-        ];
+    // reads the escape sequence that starts at the current backslash
+    fn read_escape(&mut self) -> Result<char, LexError> {
+        let initial_pos = self.pos;
+        self.next_char();
 
-        assert_tokens!(input, tokens);
+        match self.ch {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('0') => Ok('\0'),
+            Some('\\') => Ok('\\'),
+            Some('"') => Ok('"'),
+            Some('{') => Ok('{'),
+            Some('}') => Ok('}'),
+            Some('\'') => Ok('\''),
+            Some('u') => self.read_unicode_escape(initial_pos),
+            Some('x') => self.read_hex_escape(initial_pos).map(char::from),
+            _ => Err(self.invalid_escape(initial_pos)),
+        }
     }
 
-    #[test]
-    fn test_next_token_var_false() {
-        let input = "my_var3 := false";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var3")),
-            TokenType::Colon,
-            TokenType::Assign,
-            TokenType::Bool(false),
-            TokenType::EOF,
-        ];
+    // reads `\xNN` with exactly 2 hex digits, the current char is the `x`
+    fn read_hex_escape(&mut self, initial_pos: usize) -> Result<u8, LexError> {
+        let digits_pos = self.next_pos();
+        for _ in 0..2 {
+            match self.peek_char() {
+                Some(ch) if ch.is_ascii_hexdigit() => self.next_char(),
+                _ => return Err(self.invalid_escape(initial_pos)),
+            }
+        }
 
-        assert_tokens!(input, tokens);
+        let digits = &self.input[digits_pos..self.next_pos()];
+        u8::from_str_radix(digits, 16).map_err(|_| self.invalid_escape(initial_pos))
     }
 
-    #[test]
-    fn test_next_token_var_type_bool() {
-        let input = "my_var : bool = false";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var")),
-            TokenType::Colon,
-            TokenType::Type("bool".to_string()),
-            TokenType::Assign,
-            TokenType::Bool(false),
-            TokenType::EOF,
-        ];
+    // reads `\u{...}` with 1 to 6 hex digits, the current char is the `u`
+    fn read_unicode_escape(&mut self, initial_pos: usize) -> Result<char, LexError> {
+        if self.peek_char() != Some('{') {
+            return Err(self.invalid_escape(initial_pos));
+        }
 
-        assert_tokens!(input, tokens);
-    }
+        self.next_char();
+        let digits_pos = self.next_pos();
+        while let Some(true) = self.peek_char().map(|ch| ch.is_ascii_hexdigit()) {
+            self.next_char();
+        }
 
-    #[test]
-    fn test_next_token_var_type_string() {
+        let digits = &self.input[digits_pos..self.next_pos()];
+        if self.peek_char() != Some('}') {
+            return Err(self.invalid_escape(initial_pos));
+        }
+
+        self.next_char();
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(self.invalid_escape(initial_pos));
+        }
+
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.invalid_escape(initial_pos))
+    }
+
+    fn invalid_escape(&self, initial_pos: usize) -> LexError {
+        let end = self.next_pos();
+        LexError::InvalidEscape {
+            sequence: self.input[initial_pos..end].to_string(),
+            span: Span::new(initial_pos, end),
+        }
+    }
+
+    fn read_number(&mut self) -> Result<TokenType<'a>, LexError> {
+        self.read_signed_number(false)
+    }
+
+    // `-` followed by a number, only called when `lex_negative_numbers` is enabled
+    fn read_negative_number(&mut self) -> Result<TokenType<'a>, LexError> {
+        self.read_signed_number(true)
+    }
+
+    // the sign is part of the literal, so ranges are checked on the negative value,
+    // like in `-128i8`, and errors span the `-` too
+    fn read_signed_number(&mut self, negative: bool) -> Result<TokenType<'a>, LexError> {
+        let initial_pos = self.pos;
+        if negative {
+            self.next_char();
+        }
+
+        if let Some('0') = self.ch {
+            match self.peek_char() {
+                Some('x') | Some('X') => return self.read_radix_number(16, initial_pos),
+                Some('o') | Some('O') => return self.read_radix_number(8, initial_pos),
+                Some('b') | Some('B') => return self.read_radix_number(2, initial_pos),
+                _ => {}
+            }
+        }
+
+        let mut is_float = false;
+        let digits_pos = self.pos;
+        loop {
+            self.skip_ascii_while(|byte| is_digit_byte(byte) || byte == b'_');
+
+            if !is_float {
+                if let Some('.') = self.ch {
+                    if is_digit(self.peek_char()) {
+                        is_float = true;
+                        self.next_char();
+                        continue;
+                    }
+
+                    // `5.` is a float too, but `5..10` is a range and `5.foo` a member access
+                    let next = self.peek_char();
+                    if next != Some('.') && !is_ident_start(next) {
+                        is_float = true;
+                        self.next_char();
+                    }
+                }
+            }
+
+            break;
+        }
+
+        if let Some('e') | Some('E') = self.ch {
+            is_float = true;
+            self.next_char();
+
+            if let Some('+') | Some('-') = self.ch {
+                self.next_char();
+            }
+
+            if !is_digit(self.ch) {
+                return Err(self.invalid_number(initial_pos));
+            }
+
+            self.skip_ascii_while(|byte| is_digit_byte(byte) || byte == b'_');
+        }
+
+        let digits_end = self.pos;
+        let suffix = self.read_number_suffix(initial_pos)?;
+
+        // a single separator is allowed before the suffix, like in `1_000_u16`
+        let mut digits = &self.input[digits_pos..digits_end];
+        if suffix.is_some() {
+            digits = digits.strip_suffix('_').unwrap_or(digits);
+        }
+
+        // `e` is not a digit, so separators can't touch it like in `1_e5`,
+        // only decimal numbers get here so it's never a hex digit
+        let separated_exponent = ["_e", "_E", "e_", "E_"]
+            .iter()
+            .any(|pattern| digits.contains(pattern));
+        let num = strip_separators(digits)
+            .filter(|_| !separated_exponent)
+            .ok_or_else(|| self.invalid_number(initial_pos))?;
+        let num = with_sign(num, negative);
+
+        match suffix {
+            Some(suffix) if suffix.is_float() => {
+                let float_num = self.float_value(&num, initial_pos)?;
+                if !suffix.fits_float(float_num) {
+                    return Err(self.number_overflow(initial_pos));
+                }
+
+                Ok(TokenType::FloatSuffixed(float_num, suffix))
+            }
+            _ if is_float => {
+                if suffix.is_some() {
+                    return Err(self.invalid_number(initial_pos));
+                }
+
+                Ok(TokenType::Float(self.float_value(&num, initial_pos)?))
+            }
+            _ => self.int_token(&num, 10, suffix, initial_pos),
+        }
+    }
+
+    // an optional type like `u8` or `f32` right after the digits, anything else is an error
+    fn read_number_suffix(&mut self, initial_pos: usize) -> Result<Option<NumberSuffix>, LexError> {
+        if !is_letter(self.ch) {
+            return Ok(None);
+        }
+
+        let suffix_pos = self.pos;
+        while is_letter(self.ch) || is_digit(self.ch) {
+            self.next_char();
+        }
+
+        NumberSuffix::from_name(&self.input[suffix_pos..self.pos])
+            .map(Some)
+            .ok_or_else(|| self.invalid_number(initial_pos))
+    }
+
+    // any letter or digit after the prefix is part of the number, so `0b12` is an error
+    // instead of `Int(1)` followed by `Int(2)`
+    fn read_radix_number(
+        &mut self,
+        radix: u32,
+        initial_pos: usize,
+    ) -> Result<TokenType<'a>, LexError> {
+        // `initial_pos` is at the `-` of negative numbers
+        let negative = initial_pos != self.pos;
+
+        // skip the prefix
+        self.next_char();
+        self.next_char();
+
+        let digits_pos = self.pos;
+        while is_letter(self.ch) || is_digit(self.ch) {
+            self.next_char();
+        }
+
+        // `i` and `u` are never digits so they always start a suffix like in `0xffu8`
+        let text = &self.input[digits_pos..self.pos];
+        let (digits, suffix) = match text.find(['i', 'u']) {
+            Some(idx) => {
+                let suffix = NumberSuffix::from_name(&text[idx..])
+                    .ok_or_else(|| self.invalid_number(initial_pos))?;
+                (&text[..idx], Some(suffix))
+            }
+            None => (text, None),
+        };
+
+        let num = strip_separators(digits).ok_or_else(|| self.invalid_number(initial_pos))?;
+        let num = with_sign(num, negative);
+        self.int_token(&num, radix, suffix, initial_pos)
+    }
+
+    fn int_token(
+        &self,
+        num: &str,
+        radix: u32,
+        suffix: Option<NumberSuffix>,
+        initial_pos: usize,
+    ) -> Result<TokenType<'a>, LexError> {
+        let value = i64::from_str_radix(num, radix).map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                self.number_overflow(initial_pos)
+            }
+            _ => self.invalid_number(initial_pos),
+        })?;
+
+        match suffix {
+            Some(suffix) if !suffix.fits(value) => Err(self.number_overflow(initial_pos)),
+            Some(suffix) => Ok(TokenType::IntSuffixed(value, suffix)),
+            None => Ok(TokenType::Int(value)),
+        }
+    }
+
+    // floats too big for an f64, which would parse as infinity, overflow like ints do
+    fn float_value(&self, num: &str, initial_pos: usize) -> Result<f64, LexError> {
+        match num.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            Ok(_) => Err(self.number_overflow(initial_pos)),
+            Err(_) => Err(self.invalid_number(initial_pos)),
+        }
+    }
+
+    fn number_overflow(&self, initial_pos: usize) -> LexError {
+        LexError::NumberOverflow {
+            literal: self.input[initial_pos..self.pos].to_string(),
+            span: Span::new(initial_pos, self.pos),
+        }
+    }
+
+    fn invalid_number(&self, initial_pos: usize) -> LexError {
+        LexError::InvalidNumber {
+            literal: self.input[initial_pos..self.pos].to_string(),
+            span: Span::new(initial_pos, self.pos),
+        }
+    }
+
+    // whether the next char starts a number, like `5` or `.5` after the `-` of `-5` or `-.5`
+    fn peek_number(&self) -> bool {
+        let mut chars = self.input[self.pos..].chars().skip(1);
+        match chars.next() {
+            Some('.') => is_digit(chars.next()),
+            ch => is_digit(ch),
+        }
+    }
+
+    // a `-` can start a negative number at the start of the input,
+    // or after an operator, `(`, `[` or `,`
+    fn expects_operand(&self) -> bool {
+        match self.prev_kind {
+            None => true,
+            Some(kind) => {
+                kind.is_operator()
+                    || matches!(
+                        kind,
+                        TokenKind::LParen | TokenKind::LBracket | TokenKind::Comma
+                    )
+            }
+        }
+    }
+
+    // the length is checked before interning, so oversized identifiers are never stored
+    fn read_identifier(&mut self) -> Result<TokenType<'a>, LexError> {
+        let initial_pos = self.pos;
+        loop {
+            self.skip_ascii_while(|byte| is_letter_byte(byte) || is_digit_byte(byte));
+            if !is_ident_continue(self.ch) {
+                break;
+            }
+
+            self.next_char();
+        }
+
+        self.check_token_len(initial_pos)?;
+        let ident = &self.input[initial_pos..self.pos];
+        Ok(lookup_ident(ident))
+    }
+
+    // skips the rest of a malformed token, up to the next whitespace or delimiter
+    fn recover(&mut self) {
+        while let Some(ch) = self.ch {
+            if is_whitespace(ch) || is_delimiter(ch) {
+                break;
+            }
+
+            self.next_char();
+        }
+    }
+
+    // placeholder token standing where the lexer found an error
+    fn error_token(&self, err: &LexError) -> Token<'a> {
+        let span = err.span();
+        let ch = self.input[span.start..].chars().next().unwrap_or('\0');
+        let (line, column) = self.position_at(span.start);
+        Token::new(TokenType::Illegal(ch), span, line, column)
+    }
+
+    // line and column of the byte position `pos`, counted like `next_char` does from the
+    // start of the current token, or from the start of the input if `pos` is before it
+    fn position_at(&self, pos: usize) -> (usize, usize) {
+        let (start, mut line, mut column) = match self.token_start {
+            (start, ..) if start <= pos => self.token_start,
+            _ => (0, 1, 1),
+        };
+
+        let mut prev = None;
+        for ch in self.input[start..pos].chars() {
+            match ch {
+                '\n' if prev == Some('\r') => {}
+                ch if is_new_line(ch) => {
+                    line += 1;
+                    column = 1;
+                }
+                _ => column += 1,
+            }
+
+            prev = Some(ch);
+        }
+
+        (line, column)
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            // spaces and tabs, most of the whitespace, don't change the line
+            self.skip_ascii_while(|byte| byte == b' ' || byte == b'\t');
+            match self.ch {
+                Some(ch) if is_whitespace(ch) => self.next_char(),
+                _ => break,
+            }
+        }
+    }
+
+    // returns the whitespace or comment found when they are kept as tokens
+    fn skip_whitespace_and_comments(&mut self) -> Result<Option<TokenType<'a>>, LexError> {
+        loop {
+            self.token_start = (self.pos, self.line, self.column);
+            let start = self.pos;
+            self.skip_whitespace();
+            if self.options.lossless && self.pos > start {
+                let text = Cow::Borrowed(&self.input[start..self.pos]);
+                return Ok(Some(TokenType::Whitespace(text)));
+            }
+
+            self.token_start = (self.pos, self.line, self.column);
+
+            let start = self.pos;
+            match (self.ch, self.peek_char()) {
+                (Some('/'), Some('/')) => self.skip_line_comment(),
+                // `#!/usr/bin/env isy` in the first line of a script
+                (Some('#'), Some('!')) if start == 0 && self.shebang => self.skip_line_comment(),
+                (Some('#'), _) if self.options.hash_comments => self.skip_line_comment(),
+                (Some('/'), Some('*')) => {
+                    if !self.skip_block_comment() {
+                        let span = Span::new(start, self.pos);
+                        return Err(LexError::UnterminatedComment { span });
+                    }
+                }
+                _ => return Ok(None),
+            }
+
+            if self.options.keep_comments || self.options.lossless {
+                let text = Cow::Borrowed(&self.input[start..self.pos]);
+                return Ok(Some(TokenType::Comment(text)));
+            }
+        }
+    }
+
+    // block comments can be nested, `/* a /* b */ c */` is a single comment
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        while let Some(ch) = self.ch {
+            match (ch, self.peek_char()) {
+                ('/', Some('*')) => {
+                    depth += 1;
+                    self.next_char();
+                }
+                ('*', Some('/')) => {
+                    depth -= 1;
+                    self.next_char();
+                    if depth == 0 {
+                        self.next_char();
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+
+            self.next_char();
+        }
+
+        false
+    }
+
+    // consumes everything until the end of the line
+    fn skip_line_comment(&mut self) {
+        while let Some(ch) = self.ch {
+            if is_new_line(ch) {
+                break;
+            }
+
+            self.next_char();
+        }
+    }
+}
+
+/// Lexer with one token of lookahead.
+pub struct PeekableLexer<'a> {
+    lexer: Lexer<'a>,
+    peeked: Result<Token<'a>, LexError>,
+}
+
+impl<'a> PeekableLexer<'a> {
+    pub fn new(mut lexer: Lexer<'a>) -> PeekableLexer<'a> {
+        let peeked = lexer.next_token();
+        PeekableLexer { lexer, peeked }
+    }
+
+    /// Returns the token that the next call to `next_token` will return, without consuming it.
+    pub fn peek(&self) -> Result<&Token<'a>, &LexError> {
+        self.peeked.as_ref()
+    }
+
+    /// Consumes and returns the next token.
+    /// Like `Lexer::next_token` it keeps returning `EOF` at the end of the input.
+    pub fn next_token(&mut self) -> Result<Token<'a>, LexError> {
+        let next = self.lexer.next_token();
+        std::mem::replace(&mut self.peeked, next)
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Lex(LexError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "{}", err),
+            StreamError::Lex(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> StreamError {
+        StreamError::Io(err)
+    }
+}
+
+impl From<LexError> for StreamError {
+    fn from(err: LexError) -> StreamError {
+        StreamError::Lex(err)
+    }
+}
+
+/// Error reading or lexing a file, along with the path of the file.
+#[derive(Debug)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub error: StreamError,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for FileError {}
+
+/// Lexer reading its input from a `BufRead` instead of a `&str`.
+///
+/// The input is consumed line by line and only the lines of the tokens being lexed are kept
+/// in memory, so a multi-line string or comment is the largest thing ever buffered.
+/// While a comment is open, only its nesting is tracked on the lines that follow, and an
+/// open string is lexed again only from lines with a `"` that could close it.
+/// Spans are byte offsets from the start of the stream. Like `tokenize_with_errors`,
+/// lexing resumes after an error at the next whitespace or delimiter.
+pub struct StreamLexer<R> {
+    reader: R,
+    buffer: String,
+    tokens: VecDeque<Result<Token<'static>, LexError>>,
+    // bytes and lines consumed before the start of `buffer`, and the column where it starts
+    offset: usize,
+    lines: usize,
+    column: usize,
+    // string or comment left open at the end of `buffer`
+    open: Option<OpenToken>,
+    eof: bool,
+}
+
+impl<R: BufRead> StreamLexer<R> {
+    pub fn new(reader: R) -> StreamLexer<R> {
+        StreamLexer {
+            reader,
+            buffer: String::new(),
+            tokens: VecDeque::new(),
+            offset: 0,
+            lines: 0,
+            column: 1,
+            open: None,
+            eof: false,
+        }
+    }
+
+    /// Returns the next token, reading more input when needed.
+    /// Like `Lexer::next_token` it keeps returning `EOF` at the end of the stream.
+    pub fn next_token(&mut self) -> Result<Token<'static>, StreamError> {
+        while self.tokens.is_empty() {
+            let len = self.buffer.len();
+            if self.reader.read_line(&mut self.buffer)? == 0 {
+                self.eof = true;
+            }
+
+            let closed = match &mut self.open {
+                Some(OpenToken::String) => self.buffer[len..].contains('"'),
+                Some(OpenToken::Comment(depth)) => {
+                    match block_comment_depth(&self.buffer[len..], *depth) {
+                        Some(new_depth) => {
+                            *depth = new_depth;
+                            false
+                        }
+                        None => true,
+                    }
+                }
+                None => true,
+            };
+            if closed || self.eof {
+                self.lex_buffer();
+            }
+        }
+
+        // the loop above guarantees there's a token
+        Ok(self.tokens.pop_front().unwrap()?)
+    }
+
+    // lexes the buffered lines. When they end in the middle of a string or comment,
+    // the tokens before it are kept and the rest of the buffer waits for more lines
+    fn lex_buffer(&mut self) {
+        let (offset, lines, column) = (self.offset, self.lines, self.column);
+        let mut tokens = vec![];
+        let mut lexer = Lexer::new(&self.buffer);
+        lexer.shebang = offset == 0;
+        // byte position, line and column after the last token that can be kept,
+        // and how many tokens there are up to it
+        let mut complete = (0, 1, 1, 0);
+        loop {
+            // tokens inside an interpolation depend on the string around them
+            if lexer.interpolations.is_empty() {
+                let (line, col) = lexer.current_position();
+                complete = (lexer.pos, line, col, tokens.len());
+            }
+
+            match lexer.next_token() {
+                Ok(mut tok) => {
+                    let eof = tok.typ == TokenType::EOF;
+                    if eof && !self.eof {
+                        break;
+                    }
+
+                    tok.span = Span::new(tok.span.start + offset, tok.span.end + offset);
+                    if tok.line == 1 {
+                        tok.column += column - 1;
+                    }
+                    tok.line += lines;
+                    tokens.push(Ok(tok.into_owned()));
+                    if eof {
+                        break;
+                    }
+                }
+                Err(err @ LexError::UnterminatedString { .. })
+                | Err(err @ LexError::UnterminatedComment { .. })
+                    if !self.eof =>
+                {
+                    let (pos, line, col, len) = complete;
+                    tokens.truncate(len);
+                    self.open = Some(match err {
+                        // the lexer just found the comment unterminated
+                        LexError::UnterminatedComment { span } => OpenToken::Comment(
+                            block_comment_depth(&self.buffer[span.start..], 0).unwrap(),
+                        ),
+                        _ => OpenToken::String,
+                    });
+                    self.offset += pos;
+                    self.lines += line - 1;
+                    self.column = if line == 1 { column + col - 1 } else { col };
+                    self.buffer.drain(..pos);
+                    self.tokens.extend(tokens);
+                    return;
+                }
+                Err(mut err) => {
+                    let span = err.span_mut();
+                    *span = Span::new(span.start + offset, span.end + offset);
+                    tokens.push(Err(err));
+                    lexer.recover();
+                }
+            }
+        }
+
+        let (line, col) = lexer.current_position();
+        self.offset += self.buffer.len();
+        self.lines += line - 1;
+        self.column = if line == 1 { column + col - 1 } else { col };
+        self.open = None;
+        self.buffer.clear();
+        self.tokens.extend(tokens);
+    }
+}
+
+enum OpenToken {
+    String,
+    // nesting depth of the comment
+    Comment(usize),
+}
+
+// follows `Lexer::skip_block_comment` from the given depth, `None` once the comment is closed
+fn block_comment_depth(text: &str, mut depth: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i..] {
+            [b'/', b'*', ..] => {
+                depth += 1;
+                i += 2;
+            }
+            [b'*', b'/', ..] => {
+                depth -= 1;
+                if depth == 0 {
+                    return None;
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+
+    Some(depth)
+}
+
+/// Lexes the whole input at once, the final `EOF` token included.
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, LexError> {
+    Lexer::new(input).collect()
+}
+
+/// Lexes the whole input without stopping on errors. Every error is reported
+/// and replaced by an `Illegal` token, and lexing resumes at the next whitespace
+/// or delimiter.
+pub fn tokenize_with_errors(input: &str) -> (Vec<Token<'_>>, Vec<LexError>) {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = vec![];
+    let mut errors = vec![];
+    loop {
+        match lexer.next_token() {
+            Ok(tok) => {
+                let eof = tok.typ == TokenType::EOF;
+                tokens.push(tok);
+                if eof {
+                    break;
+                }
+            }
+            Err(err) => {
+                tokens.push(lexer.error_token(&err));
+                errors.push(err);
+                lexer.recover();
+            }
+        }
+    }
+
+    (tokens, errors)
+}
+
+/// Reads and lexes the file at `path`, errors mention the path of the file.
+pub fn tokenize_file(path: &Path) -> Result<Vec<Token<'static>>, FileError> {
+    let file_error = |error: StreamError| FileError {
+        path: path.to_path_buf(),
+        error,
+    };
+
+    let src = std::fs::read_to_string(path).map_err(|err| file_error(err.into()))?;
+    Lexer::new(&src)
+        .map(|tok| tok.map(Token::into_owned))
+        .collect::<Result<_, _>>()
+        .map_err(|err| file_error(err.into()))
+}
+
+/// Lexes the input into a JSON array with the type, lexeme, span and position of
+/// every token, so tools outside of Rust can consume them. Invalid tokens are
+/// reported as `Illegal` like `tokenize_with_errors` does.
+#[cfg(feature = "serde")]
+pub fn tokens_to_json(src: &str) -> String {
+    #[derive(serde::Serialize)]
+    struct JsonToken<'t, 'a> {
+        #[serde(rename = "type")]
+        typ: &'t TokenType<'a>,
+        lexeme: &'a str,
+        span: Span,
+        line: usize,
+        column: usize,
+    }
+
+    let (tokens, _) = tokenize_with_errors(src);
+    let tokens: Vec<JsonToken> = tokens
+        .iter()
+        .map(|tok| JsonToken {
+            typ: &tok.typ,
+            lexeme: tok.lexeme(src),
+            span: tok.span,
+            line: tok.line,
+            column: tok.column,
+        })
+        .collect();
+
+    serde_json::to_string(&tokens).expect("tokens always serialize to JSON")
+}
+
+/// Yields every token of the input, including the final `EOF` token once,
+/// and then returns `None`. The iteration also stops after the first error.
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let tok = self.next_token();
+        match &tok {
+            Ok(tok) if tok.typ != TokenType::EOF => {}
+            _ => self.finished = true,
+        }
+
+        Some(tok)
+    }
+}
+
+/// Reserved words that lex to their own token instead of an `Ident`.
+pub const KEYWORDS: &[(&str, TokenType<'static>)] = &[
+    ("true", TokenType::Bool(true)),
+    ("false", TokenType::Bool(false)),
+    ("null", TokenType::Null),
+    ("if", TokenType::If),
+    ("else", TokenType::Else),
+    ("while", TokenType::While),
+    ("for", TokenType::For),
+    ("fn", TokenType::Fn),
+    ("return", TokenType::Return),
+    ("let", TokenType::Let),
+    ("const", TokenType::Const),
+    ("break", TokenType::Break),
+    ("continue", TokenType::Continue),
+];
+
+/// Names of the built-in types, lexed as `Type`.
+pub const TYPES: &[&str] = &["bool", "int", "float", "string", "char"];
+
+/// Returns true if `ident` is a reserved word (a keyword or a type name)
+/// and therefore can't be used as an identifier.
+pub fn is_keyword(ident: &str) -> bool {
+    KEYWORDS.iter().any(|(k, _)| *k == ident) || TYPES.contains(&ident)
+}
+
+fn lookup_ident(ident: &str) -> TokenType<'_> {
+    if let Some((_, typ)) = KEYWORDS.iter().find(|(k, _)| *k == ident) {
+        return typ.clone();
+    }
+
+    if TYPES.contains(&ident) {
+        return TokenType::Type(Cow::Borrowed(ident));
+    }
+
+    TokenType::Ident(Symbol::intern(ident))
+}
+
+// the digits are parsed along with the sign so `-9223372036854775808` fits an int
+fn with_sign(num: Cow<'_, str>, negative: bool) -> Cow<'_, str> {
+    if negative {
+        Cow::Owned(format!("-{}", num))
+    } else {
+        num
+    }
+}
+
+// underscores are allowed only between digits, `1_000` is valid but `1_`, `1__0` or `1_.5` are not
+// numbers without underscores, the vast majority, are borrowed instead of copied
+fn strip_separators(num: &str) -> Option<Cow<'_, str>> {
+    if !num.contains('_') {
+        return Some(Cow::Borrowed(num));
+    }
+
+    let invalid = num.starts_with('_')
+        || num.ends_with('_')
+        || num.contains("__")
+        || num.contains("_.")
+        || num.contains("._");
+
+    if invalid {
+        return None;
+    }
+
+    Some(Cow::Owned(num.replace('_', "")))
+}
+
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\t') || is_new_line(ch)
+}
+
+fn is_delimiter(ch: char) -> bool {
+    matches!(ch, '(' | ')' | '{' | '}' | '[' | ']' | ',' | ';')
+}
+
+// a lone `\r` is a line break too, like in old Mac files
+pub(crate) fn is_new_line(ch: char) -> bool {
+    matches!(ch, '\n' | '\r')
+}
+
+#[cfg(not(feature = "unicode-idents"))]
+fn is_ident_start(ch: Option<char>) -> bool {
+    is_letter(ch)
+}
+
+#[cfg(not(feature = "unicode-idents"))]
+fn is_ident_continue(ch: Option<char>) -> bool {
+    is_letter(ch) || is_digit(ch)
+}
+
+// identifiers follow the unicode XID rules, allowing names like `café` or `名前`
+// ascii chars are checked first so the common case skips the unicode tables
+#[cfg(feature = "unicode-idents")]
+fn is_ident_start(ch: Option<char>) -> bool {
+    use unicode_xid::UnicodeXID;
+    match ch {
+        Some(ch) if ch.is_ascii() => ch.is_ascii_alphabetic() || ch == '_',
+        Some(ch) => ch.is_xid_start(),
+        None => false,
+    }
+}
+
+#[cfg(feature = "unicode-idents")]
+fn is_ident_continue(ch: Option<char>) -> bool {
+    use unicode_xid::UnicodeXID;
+    match ch {
+        Some(ch) if ch.is_ascii() => ch.is_ascii_alphanumeric() || ch == '_',
+        Some(ch) => ch.is_xid_continue(),
+        None => false,
+    }
+}
+
+fn is_letter(ch: Option<char>) -> bool {
+    matches!(ch, Some(ch) if ch.is_ascii() && is_letter_byte(ch as u8))
+}
+
+fn is_digit(ch: Option<char>) -> bool {
+    matches!(ch, Some(ch) if ch.is_ascii() && is_digit_byte(ch as u8))
+}
+
+// byte versions of `is_letter` and `is_digit` for the ascii fast paths
+fn is_letter_byte(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_'
+}
+
+fn is_digit_byte(byte: u8) -> bool {
+    byte.is_ascii_digit()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    macro_rules! assert_tokens {
+        ($input:expr, $tokens:expr) => {{
+            let mut lexer = Lexer::new($input);
+            $tokens.iter().enumerate().for_each(|(i, t)| {
+                let tok = lexer.next_token().unwrap();
+                assert_eq!(tok.typ, *t, "Wrong token type at index: {}", i);
+            });
+        }};
+    }
+
+    #[test]
+    fn test_next_token_var_int() {
+        let input = "my_var := 10";
+        let tests = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Int(10),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tests);
+    }
+
+    #[test]
+    fn test_next_token_var_float() {
+        let input = "my_var3 := 99.0";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var3")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Float(99.0),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_string() {
+        let input = "my_var := \"hello\"";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::String("hello".into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_true() {
+        let input = "my_var3 := true";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var3")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Bool(true),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_false() {
+        let input = "my_var3 := false";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var3")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Bool(false),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_type_bool() {
+        let input = "my_var : bool = false";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Type("bool".into()),
+            TokenType::Assign,
+            TokenType::Bool(false),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_type_string() {
         let input = "my_var : string = \"hello\"";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var")),
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Type("string".into()),
+            TokenType::Assign,
+            TokenType::String("hello".into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_type_int() {
+        let input = "my_var : int = 10";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Type("int".into()),
+            TokenType::Assign,
+            TokenType::Int(10),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_var_type_float() {
+        let input = "my_var : float = 10.123456";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Type("float".into()),
+            TokenType::Assign,
+            TokenType::Float(10.123456),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+    #[test]
+    fn test_next_token_int_operators() {
+        let input = "1 + 2 - 3 * 4 / 5 % 6";
+        let tokens = [
+            TokenType::Int(1),
+            TokenType::Plus,
+            TokenType::Int(2),
+            TokenType::Minus,
+            TokenType::Int(3),
+            TokenType::Asterisk,
+            TokenType::Int(4),
+            TokenType::Slash,
+            TokenType::Int(5),
+            TokenType::Percent,
+            TokenType::Int(6),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_float_operators() {
+        let input = "1.12 + 2.23 - 3.34 * 4.45 / 5.56 % 6.67";
+        let tokens = [
+            TokenType::Float(1.12),
+            TokenType::Plus,
+            TokenType::Float(2.23),
+            TokenType::Minus,
+            TokenType::Float(3.34),
+            TokenType::Asterisk,
+            TokenType::Float(4.45),
+            TokenType::Slash,
+            TokenType::Float(5.56),
+            TokenType::Percent,
+            TokenType::Float(6.67),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_string() {
+        let input = r#""hello!" + "bye!""#;
+        let tokens = [
+            TokenType::String("hello!".into()),
+            TokenType::Plus,
+            TokenType::String("bye!".into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_string_escape() {
+        let input = r#""escape this \" please""#;
+        let tokens = [
+            TokenType::String(r#"escape this " please"#.into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_position() {
+        let input = "my_var := \"multi\nline\" other";
+        let positions = [(1, 1), (1, 8), (1, 9), (1, 11), (2, 7), (2, 12)];
+
+        let mut lexer = Lexer::new(input);
+        positions
+            .iter()
+            .enumerate()
+            .for_each(|(i, (line, column))| {
+                let tok = lexer.next_token().unwrap();
+                assert_eq!(
+                    (tok.line, tok.column),
+                    (*line, *column),
+                    "Wrong position at index: {}",
+                    i
+                );
+            });
+    }
+
+    #[test]
+    fn test_next_token_crlf_position() {
+        let tokens = tokenize("a\r\nb").unwrap();
+        assert_eq!(tokens[1].typ, TokenType::Ident(Symbol::intern("b")));
+        assert_eq!((tokens[1].line, tokens[1].column), (2, 1));
+
+        // a lone `\r` breaks the line too, `\r\r\n` is two breaks
+        let input = "a\rb\r\r\nc // d\r\ne \"f\\\r\ng\" h";
+        let positions = [(1, 1), (2, 1), (4, 1), (5, 1), (5, 3), (6, 4), (6, 5)];
+        let tokens = tokenize(input).unwrap();
+        assert_eq!(tokens[4].typ, TokenType::String("fg".into()));
+        tokens
+            .iter()
+            .zip(positions.iter())
+            .for_each(|(tok, (line, column))| {
+                assert_eq!((tok.line, tok.column), (*line, *column), "{:?}", tok.typ);
+            });
+    }
+
+    #[test]
+    fn test_next_token_eof_position() {
+        let input = "a := 1\n// the end\n  \n";
+        let mut lexer = Lexer::new(input);
+        assert_eq!(lexer.current_position(), (1, 1));
+
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.current_position(), (1, 2));
+
+        let eof = lexer.find(|tok| tok.as_ref().unwrap().typ == TokenType::EOF);
+        let eof = eof.unwrap().unwrap();
+        assert_eq!(eof.span, Span::new(input.len(), input.len()));
+        assert_eq!((eof.line, eof.column), (4, 1));
+        assert_eq!(lexer.current_position(), (4, 1));
+
+        // once exhausted the lexer stays at the end
+        let mut lexer = Lexer::new("x");
+        lexer.next_token().unwrap();
+        (0..2).for_each(|_| {
+            let eof = lexer.next_token().unwrap();
+            assert_eq!(eof.span, Span::new(1, 1));
+            assert_eq!((eof.line, eof.column), (1, 2));
+        });
+    }
+
+    #[test]
+    fn test_next_token_span() {
+        let input = "my_var := \"hello\"";
+        let spans = [(0, 6), (7, 8), (8, 9), (10, 17), (17, 17)];
+
+        let mut lexer = Lexer::new(input);
+        spans.iter().enumerate().for_each(|(i, (start, end))| {
+            let tok = lexer.next_token().unwrap();
+            assert_eq!(
+                tok.span,
+                Span::new(*start, *end),
+                "Wrong span at index: {}",
+                i
+            );
+        });
+    }
+
+    #[test]
+    fn test_iterator() {
+        let tokens: Vec<TokenType> = Lexer::new("a := 1").map(|t| t.unwrap().typ).collect();
+        assert_eq!(
+            tokens,
+            [
+                TokenType::Ident(Symbol::intern("a")),
+                TokenType::Colon,
+                TokenType::Assign,
+                TokenType::Int(1),
+                TokenType::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("my_var := 10").unwrap();
+        assert_eq!(tokens.len(), 5);
+
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.typ).collect();
+        assert_eq!(
+            types,
+            [
+                TokenType::Ident(Symbol::intern("my_var")),
+                TokenType::Colon,
+                TokenType::Assign,
+                TokenType::Int(10),
+                TokenType::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_token_comment_end_of_line() {
+        let input = "my_var := 10 // ten";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Int(10),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_comment_own_line() {
+        let input = "// the answer\n// is coming\nmy_var := 42";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Int(42),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_comment_division() {
+        let input = "10 / 2 // half";
+        let tokens = [
+            TokenType::Int(10),
+            TokenType::Slash,
+            TokenType::Int(2),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_block_comment() {
+        let input = "my_var /* the\nanswer */ := 42";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Int(42),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+
+        let mut lexer = Lexer::new(input);
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.next_token().unwrap().line, 2);
+    }
+
+    #[test]
+    fn test_next_token_block_comment_nested() {
+        let input = "1 /* outer /* inner */ still outer */ * 2";
+        let tokens = [
+            TokenType::Int(1),
+            TokenType::Asterisk,
+            TokenType::Int(2),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_block_comment_unterminated() {
+        let input = "1 /* never /* closed */";
+        let mut lexer = Lexer::new(input);
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::Int(1));
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::UnterminatedComment {
+                span: Span::new(2, 23)
+            }
+        );
+    }
+
+    #[test]
+    fn test_next_token_hex() {
+        let input = "0xff 0X1A2B 0x1a2b";
+        let tokens = [
+            TokenType::Int(255),
+            TokenType::Int(6699),
+            TokenType::Int(6699),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_zero() {
+        let input = "0 + x0";
+        let tokens = [
+            TokenType::Int(0),
+            TokenType::Plus,
+            TokenType::Ident(Symbol::intern("x0")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_binary() {
+        let input = "0b1010 0B11";
+        let tokens = [TokenType::Int(10), TokenType::Int(3), TokenType::EOF];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_octal() {
+        let input = "0o755 0O17";
+        let tokens = [TokenType::Int(493), TokenType::Int(15), TokenType::EOF];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_read_number_invalid_digits() {
+        assert!(Lexer::new("0b2").read_number().is_err());
+        assert!(Lexer::new("0o8").read_number().is_err());
+        assert!(Lexer::new("0xfg").read_number().is_err());
+        assert!(Lexer::new("0x").read_number().is_err());
+    }
+
+    #[test]
+    fn test_next_token_digit_separators() {
+        let input = "1_000 1_000.000_1 0xff_ff _1";
+        let tokens = [
+            TokenType::Int(1000),
+            TokenType::Float(1000.0001),
+            TokenType::Int(65535),
+            TokenType::Ident(Symbol::intern("_1")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_read_number_invalid_separators() {
+        assert!(Lexer::new("1_").read_number().is_err());
+        assert!(Lexer::new("1__0").read_number().is_err());
+        assert!(Lexer::new("1_.5").read_number().is_err());
+        assert!(Lexer::new("0x_ff").read_number().is_err());
+        assert!(Lexer::new("1_e5").read_number().is_err());
+        assert!(Lexer::new("2.5_E3").read_number().is_err());
+        assert!(Lexer::new("1e_5").read_number().is_err());
+        assert_eq!(
+            Lexer::new("1_0e1_0").read_number(),
+            Ok(TokenType::Float(1e11))
+        );
+        assert_eq!(Lexer::new("0xe_e").read_number(), Ok(TokenType::Int(0xee)));
+
+        let err = Lexer::new("1_e5 x").next_token().unwrap_err();
+        assert_eq!(
+            err,
+            LexError::InvalidNumber {
+                literal: "1_e5".to_string(),
+                span: Span::new(0, 4),
+            }
+        );
+    }
+
+    #[test]
+    fn test_next_token_exponent() {
+        let input = "1e10 2.5e-3 6.022E23 1e+2";
+        let tokens = [
+            TokenType::Float(1e10),
+            TokenType::Float(2.5e-3),
+            TokenType::Float(6.022e23),
+            TokenType::Float(100.0),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_read_number_missing_exponent() {
+        assert!(Lexer::new("1e").read_number().is_err());
+        assert!(Lexer::new("1e-").read_number().is_err());
+        assert!(Lexer::new("2.5E+x").read_number().is_err());
+    }
+
+    #[test]
+    fn test_next_token_number_suffixes() {
+        let input = "10i64 2.5f32 255u8 1_000_u16 0xffu8 1e3f64 7f32";
+        let tokens = [
+            TokenType::IntSuffixed(10, NumberSuffix::I64),
+            TokenType::FloatSuffixed(2.5, NumberSuffix::F32),
+            TokenType::IntSuffixed(255, NumberSuffix::U8),
+            TokenType::IntSuffixed(1000, NumberSuffix::U16),
+            TokenType::IntSuffixed(255, NumberSuffix::U8),
+            TokenType::FloatSuffixed(1000.0, NumberSuffix::F64),
+            TokenType::FloatSuffixed(7.0, NumberSuffix::F32),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_read_number_invalid_suffix() {
+        let err = Lexer::new("10x32").read_number().unwrap_err();
+        assert_eq!(
+            err,
+            LexError::InvalidNumber {
+                literal: "10x32".to_string(),
+                span: Span::new(0, 5),
+            }
+        );
+
+        assert!(Lexer::new("2.5i32").read_number().is_err());
+        assert!(Lexer::new("0b1u7").read_number().is_err());
+        assert!(matches!(
+            Lexer::new("256u8").read_number(),
+            Err(LexError::NumberOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_number_float_range() {
+        let tests = [
+            ("1e40f32", None),
+            ("3.5e38f32", None),
+            ("1e400f64", None),
+            ("1e400", None),
+            (
+                "3.4e38f32",
+                Some(TokenType::FloatSuffixed(3.4e38, NumberSuffix::F32)),
+            ),
+            (
+                "1e40f64",
+                Some(TokenType::FloatSuffixed(1e40, NumberSuffix::F64)),
+            ),
+            ("1e40", Some(TokenType::Float(1e40))),
+            // plain and suffixed floats share the f64 value
+            ("0.1", Some(TokenType::Float(0.1))),
+            (
+                "0.1f32",
+                Some(TokenType::FloatSuffixed(0.1, NumberSuffix::F32)),
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            let result = Lexer::new(input).read_number();
+            match expected {
+                Some(typ) => assert_eq!(result.as_ref(), Ok(typ), "{}", input),
+                None => assert!(
+                    matches!(result, Err(LexError::NumberOverflow { .. })),
+                    "{}",
+                    input
+                ),
+            }
+        });
+    }
+
+    #[test]
+    fn test_next_token_leading_dot_float() {
+        let input = ".5 a.5 0..5 x = .25e2 (a).0 a .5 .foo";
+        let tokens = [
+            TokenType::Float(0.5),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Dot,
+            TokenType::Int(5),
+            TokenType::Int(0),
+            TokenType::DotDot,
+            TokenType::Int(5),
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::Assign,
+            TokenType::Float(25.0),
+            TokenType::LParen,
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::RParen,
+            TokenType::Dot,
+            TokenType::Int(0),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Float(0.5),
+            TokenType::Dot,
+            TokenType::Ident(Symbol::intern("foo")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_trailing_dot_float() {
+        let input = "5. 5.0 5..10 (5.) 5.foo 1.5.";
+        let tokens = [
+            TokenType::Float(5.0),
+            TokenType::Float(5.0),
+            TokenType::Int(5),
+            TokenType::DotDot,
+            TokenType::Int(10),
+            TokenType::LParen,
+            TokenType::Float(5.0),
+            TokenType::RParen,
+            TokenType::Int(5),
+            TokenType::Dot,
+            TokenType::Ident(Symbol::intern("foo")),
+            TokenType::Float(1.5),
+            TokenType::Dot,
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_power_and_percent() {
+        let input = "2 ** 8 * 3 % 2 %= 1";
+        let tokens = [
+            TokenType::Int(2),
+            TokenType::Power,
+            TokenType::Int(8),
+            TokenType::Asterisk,
+            TokenType::Int(3),
+            TokenType::Percent,
+            TokenType::Int(2),
+            TokenType::PercentAssign,
+            TokenType::Int(1),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_ternary() {
+        let input = "a ? b : c?";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Question,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::Colon,
+            TokenType::Ident(Symbol::intern("c")),
+            TokenType::Question,
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_at() {
+        let input = "@foo bar @ @deprecated";
+        let tokens = [
+            TokenType::At,
+            TokenType::Ident(Symbol::intern("foo")),
+            TokenType::Ident(Symbol::intern("bar")),
+            TokenType::At,
+            TokenType::At,
+            TokenType::Ident(Symbol::intern("deprecated")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_number_raw() {
+        let input = "0xFF + 1_000 2.5e3 7u8 1.0f64 \"0x1\"";
+        let tokens = tokenize(input).unwrap();
+        let expected = [
+            Some("0xFF"),
+            None,
+            Some("1_000"),
+            Some("2.5e3"),
+            Some("7u8"),
+            Some("1.0f64"),
+            None,
+            None,
+        ];
+
+        assert_eq!(tokens[0].typ, TokenType::Int(255));
+        tokens.iter().zip(expected.iter()).for_each(|(tok, raw)| {
+            assert_eq!(tok.raw.as_deref(), *raw, "{:?}", tok.typ);
+        });
+
+        // the raw text outlives the input
+        let tok = tokens[0].clone().into_owned();
+        drop(tokens);
+        assert_eq!(tok.raw.as_deref(), Some("0xFF"));
+    }
+
+    #[test]
+    fn test_next_token_big_int() {
+        let input = "3000000000 0xffffffffff";
+        let tokens = [
+            TokenType::Int(3_000_000_000),
+            TokenType::Int(0xff_ffff_ffff),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_number_overflow() {
+        let input = "a := 99999999999999999999";
+        let mut lexer = Lexer::new(input);
+        (0..3).for_each(|_| {
+            lexer.next_token().unwrap();
+        });
+
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::NumberOverflow {
+                literal: "99999999999999999999".to_string(),
+                span: Span::new(5, 25),
+            }
+        );
+    }
+
+    #[test]
+    fn test_iterator_stops_on_error() {
+        let tokens: Vec<Result<Token, LexError>> = Lexer::new("1 0b2 3").collect();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens[0].is_ok());
+        assert!(tokens[1].is_err());
+
+        assert!(tokenize("1 0b2 3").is_err());
+    }
+
+    #[test]
+    fn test_next_token_unterminated_string() {
+        let input = "a := \"no closing quote";
+        let mut lexer = Lexer::new(input);
+        (0..3).for_each(|_| {
+            lexer.next_token().unwrap();
+        });
+
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::UnterminatedString {
+                span: Span::new(5, 22)
+            }
+        );
+
+        let input = r#""escaped quote \""#;
+        assert!(Lexer::new(input).next_token().is_err());
+    }
+
+    #[test]
+    fn test_next_token_string_escape_sequences() {
+        let input = r#""\n" "\t" "\r" "\\" "\"" "\0" "a\tb\\c""#;
+        let tokens = [
+            TokenType::String("\n".into()),
+            TokenType::String("\t".into()),
+            TokenType::String("\r".into()),
+            TokenType::String("\\".into()),
+            TokenType::String("\"".into()),
+            TokenType::String("\0".into()),
+            TokenType::String("a\tb\\c".into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_string_invalid_escape() {
+        let input = r#""bad \q escape""#;
+        assert_eq!(
+            Lexer::new(input).next_token().unwrap_err(),
+            LexError::InvalidEscape {
+                sequence: r"\q".to_string(),
+                span: Span::new(5, 7),
+            }
+        );
+    }
+
+    #[test]
+    fn test_next_token_string_unicode_escape() {
+        let input = r#""\u{41}\u{e9}" "\u{1F600}""#;
+        let tokens = [
+            TokenType::String("Aé".into()),
+            TokenType::String("😀".into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_string_hex_escape() {
+        let input = r#""\x41\x42" "\xe9\x00" '\x7F'"#;
+        let tokens = [
+            TokenType::String("AB".into()),
+            TokenType::String("é\0".into()),
+            TokenType::Char('\x7f'),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+
+        let inputs = [
+            (r#""\xZZ""#, r"\x"),
+            (r#""\x4""#, r"\x4"),
+            (r#""\x4g""#, r"\x4"),
+        ];
+
+        inputs
+            .iter()
+            .for_each(|(input, sequence)| match Lexer::new(input).next_token() {
+                Err(LexError::InvalidEscape { sequence: s, .. }) => assert_eq!(s, *sequence),
+                res => panic!("Expected invalid escape for {}, got {:?}", input, res),
+            });
+    }
+
+    #[test]
+    fn test_next_token_string_invalid_unicode_escape() {
+        let inputs = [
+            (r#""\u{}""#, r"\u{}"),
+            (r#""\u{110000}""#, r"\u{110000}"),
+            (r#""\u{1234567}""#, r"\u{1234567}"),
+            (r#""\u41""#, r"\u"),
+            (r#""\u{41""#, r"\u{41"),
+        ];
+
+        inputs
+            .iter()
+            .for_each(|(input, sequence)| match Lexer::new(input).next_token() {
+                Err(LexError::InvalidEscape { sequence: s, .. }) => assert_eq!(s, *sequence),
+                res => panic!("Expected invalid escape for {}, got {:?}", input, res),
+            });
+    }
+
+    #[test]
+    fn test_next_token_char() {
+        let input = r"'a' '\n' '\'' '\u{41}' 'é'";
+        let tokens = [
+            TokenType::Char('a'),
+            TokenType::Char('\n'),
+            TokenType::Char('\''),
+            TokenType::Char('A'),
+            TokenType::Char('é'),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_invalid_char() {
+        let inputs = [("''", (0, 2)), ("'ab'", (0, 3)), ("'a", (0, 2))];
+
+        inputs.iter().for_each(|(input, (start, end))| {
+            assert_eq!(
+                Lexer::new(input).next_token().unwrap_err(),
+                LexError::InvalidChar {
+                    span: Span::new(*start, *end)
+                },
+                "Wrong error for {}",
+                input
+            );
+        });
+    }
+
+    #[test]
+    fn test_next_token_var_type_char() {
+        let input = "my_var : char = 'a'";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("my_var")),
+            TokenType::Colon,
+            TokenType::Type("char".into()),
+            TokenType::Assign,
+            TokenType::Char('a'),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_equality() {
+        let input = "a == b != c";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Equal,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::NotEqual,
+            TokenType::Ident(Symbol::intern("c")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_assign_vs_equal() {
+        let tests = [
+            ("a = b", [TokenType::Assign].as_ref()),
+            ("a == b", &[TokenType::Equal]),
+            ("a === b", &[TokenType::Equal, TokenType::Assign]),
+            ("a ==== b", &[TokenType::Equal, TokenType::Equal]),
+            ("a => b", &[TokenType::FatArrow]),
+            ("a ==> b", &[TokenType::Equal, TokenType::Gt]),
+            ("a =!= b", &[TokenType::Assign, TokenType::NotEqual]),
+        ];
+
+        tests.iter().for_each(|(input, ops)| {
+            let types: Vec<TokenType> = tokenize(input)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.typ)
+                .collect();
+
+            let mut expected = vec![TokenType::Ident(Symbol::intern("a"))];
+            expected.extend_from_slice(ops);
+            expected.push(TokenType::Ident(Symbol::intern("b")));
+            expected.push(TokenType::EOF);
+            assert_eq!(types, expected, "{}", input);
+        });
+
+        // no whitespace needed, every char is consumed exactly once
+        let tokens = tokenize("a===b").unwrap();
+        let spans: Vec<Span> = tokens.iter().map(|t| t.span).collect();
+        assert_eq!(
+            spans,
+            [
+                Span::new(0, 1),
+                Span::new(1, 3),
+                Span::new(3, 4),
+                Span::new(4, 5),
+                Span::new(5, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_token_comparison() {
+        let input = "1 < 2 3 >= 3 a<=b c>d";
+        let tokens = [
+            TokenType::Int(1),
+            TokenType::Lt,
+            TokenType::Int(2),
+            TokenType::Int(3),
+            TokenType::Gte,
+            TokenType::Int(3),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Lte,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::Ident(Symbol::intern("c")),
+            TokenType::Gt,
+            TokenType::Ident(Symbol::intern("d")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_logical() {
+        let input = "true && false a || b";
+        let tokens = [
+            TokenType::Bool(true),
+            TokenType::And,
+            TokenType::Bool(false),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Or,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_lone_ampersand() {
+        let input = "a & b";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::BitAnd,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_bitwise() {
+        let input = "a & b | c ^ ~d";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::BitAnd,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::BitOr,
+            TokenType::Ident(Symbol::intern("c")),
+            TokenType::BitXor,
+            TokenType::BitNot,
+            TokenType::Ident(Symbol::intern("d")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_shifts() {
+        let input = "a << 2 >> 1 <= 4";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Shl,
+            TokenType::Int(2),
+            TokenType::Shr,
+            TokenType::Int(1),
+            TokenType::Lte,
+            TokenType::Int(4),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_delimiters() {
+        let input = "(a + b) * [c] { x := 1 }";
+        let tokens = [
+            TokenType::LParen,
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Plus,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::RParen,
+            TokenType::Asterisk,
+            TokenType::LBracket,
+            TokenType::Ident(Symbol::intern("c")),
+            TokenType::RBracket,
+            TokenType::LBrace,
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Int(1),
+            TokenType::RBrace,
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_separators() {
+        let input = "a, b, c;";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Comma,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::Comma,
+            TokenType::Ident(Symbol::intern("c")),
+            TokenType::Semicolon,
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_dot() {
+        let input = "a.b 1.5 1.foo x.len()";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Dot,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::Float(1.5),
+            TokenType::Int(1),
+            TokenType::Dot,
+            TokenType::Ident(Symbol::intern("foo")),
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::Dot,
+            TokenType::Ident(Symbol::intern("len")),
+            TokenType::LParen,
+            TokenType::RParen,
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_ranges() {
+        let input = "0..10 0..=10 1.0..2.0 a..b";
+        let tokens = [
+            TokenType::Int(0),
+            TokenType::DotDot,
+            TokenType::Int(10),
+            TokenType::Int(0),
+            TokenType::DotDotEq,
+            TokenType::Int(10),
+            TokenType::Float(1.0),
+            TokenType::DotDot,
+            TokenType::Float(2.0),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::DotDot,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_arrow() {
+        let input = "-> int a - > b";
+        let tokens = [
+            TokenType::Arrow,
+            TokenType::Type("int".into()),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Minus,
+            TokenType::Gt,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_fat_arrow() {
+        let input = "x => y = = == = >";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::FatArrow,
+            TokenType::Ident(Symbol::intern("y")),
+            TokenType::Assign,
+            TokenType::Assign,
+            TokenType::Equal,
+            TokenType::Assign,
+            TokenType::Gt,
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_compound_assign() {
+        let input = "a += 1 -= 2 *= 3 /= 4 %= 5";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::PlusAssign,
+            TokenType::Int(1),
+            TokenType::MinusAssign,
+            TokenType::Int(2),
+            TokenType::AsteriskAssign,
+            TokenType::Int(3),
+            TokenType::SlashAssign,
+            TokenType::Int(4),
+            TokenType::PercentAssign,
+            TokenType::Int(5),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_compound_assign_spaced() {
+        let input = "a + = b";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Plus,
+            TokenType::Assign,
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_peek_after_multibyte() {
+        let input = "'é' <= 'ü' != 'ñ'";
+        let tokens = [
+            TokenType::Char('é'),
+            TokenType::Lte,
+            TokenType::Char('ü'),
+            TokenType::NotEqual,
+            TokenType::Char('ñ'),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_multibyte() {
+        let input = "\"héllo\" '\\é'";
+        let mut lexer = Lexer::new(input);
+        assert_eq!(
+            lexer.next_token().unwrap().typ,
+            TokenType::String("héllo".into())
+        );
+
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::InvalidEscape {
+                sequence: "\\é".to_string(),
+                span: Span::new(10, 13),
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "unicode-idents"))]
+    fn test_next_token_ascii_ident() {
+        let input = "café";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("caf")),
+            TokenType::Illegal('é'),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-idents")]
+    fn test_next_token_unicode_ident() {
+        let input = "café := λόγος + 名前2";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("café")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Ident(Symbol::intern("λόγος")),
+            TokenType::Plus,
+            TokenType::Ident(Symbol::intern("名前2")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_mixed_whitespace() {
+        let input = "a\t:=\r\n1\n\n  b";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::Colon,
+            TokenType::Assign,
+            TokenType::Int(1),
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_control_flow_keywords() {
+        let input = "if iffy else elsewhere while whiles for format";
+        let tokens = [
+            TokenType::If,
+            TokenType::Ident(Symbol::intern("iffy")),
+            TokenType::Else,
+            TokenType::Ident(Symbol::intern("elsewhere")),
+            TokenType::While,
+            TokenType::Ident(Symbol::intern("whiles")),
+            TokenType::For,
+            TokenType::Ident(Symbol::intern("format")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_function_keywords() {
+        let input = "fn foo return x function returned";
+        let tokens = [
+            TokenType::Fn,
+            TokenType::Ident(Symbol::intern("foo")),
+            TokenType::Return,
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::Ident(Symbol::intern("function")),
+            TokenType::Ident(Symbol::intern("returned")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_let() {
+        let input = "let x: int = 5";
+        let tokens = [
+            TokenType::Let,
+            TokenType::Ident(Symbol::intern("x")),
             TokenType::Colon,
-            TokenType::Type("string".to_string()),
+            TokenType::Type("int".into()),
             TokenType::Assign,
-            TokenType::String("hello".to_string()),
+            TokenType::Int(5),
             TokenType::EOF,
         ];
 
@@ -313,14 +2998,15 @@ mod test {
     }
 
     #[test]
-    fn test_next_token_var_type_int() {
-        let input = "my_var : int = 10";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var")),
+    fn test_next_token_const() {
+        let input = "const SCALE: float = 2.5";
+        let tokens = [
+            TokenType::Const,
+            TokenType::Ident(Symbol::intern("SCALE")),
             TokenType::Colon,
-            TokenType::Type("int".to_string()),
+            TokenType::Type("float".into()),
             TokenType::Assign,
-            TokenType::Int(10),
+            TokenType::Float(2.5),
             TokenType::EOF,
         ];
 
@@ -328,34 +3014,668 @@ mod test {
     }
 
     #[test]
-    fn test_next_token_var_type_float() {
-        let input = "my_var : float = 10.123456";
-        let tokens = vec![
-            TokenType::Ident(String::from("my_var")),
+    fn test_next_token_loop_keywords() {
+        let input = "break continue breakpoint";
+        let tokens = [
+            TokenType::Break,
+            TokenType::Continue,
+            TokenType::Ident(Symbol::intern("breakpoint")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_null() {
+        let input = "x := null";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("x")),
             TokenType::Colon,
-            TokenType::Type("float".to_string()),
             TokenType::Assign,
-            TokenType::Float(10.123456),
+            TokenType::Null,
             TokenType::EOF,
         ];
 
         assert_tokens!(input, tokens);
     }
+
     #[test]
-    fn test_next_token_int_operators() {
-        let input = "1 + 2 - 3 * 4 / 5 % 6";
-        let tokens = vec![
-            TokenType::Int(1),
-            TokenType::Plus,
-            TokenType::Int(2),
-            TokenType::Minus,
-            TokenType::Int(3),
-            TokenType::Asterisk,
-            TokenType::Int(4),
-            TokenType::Slash,
-            TokenType::Int(5),
-            TokenType::Module,
-            TokenType::Int(6),
+    fn test_is_keyword() {
+        KEYWORDS
+            .iter()
+            .for_each(|(k, _)| assert!(is_keyword(k), "{}", k));
+        TYPES.iter().for_each(|t| assert!(is_keyword(t), "{}", t));
+
+        assert!(!is_keyword("my_var"));
+        assert!(!is_keyword("iffy"));
+    }
+
+    #[test]
+    fn test_token_lexeme() {
+        let input = "a := 0xFF + 1_000 + \"a\\tb\"";
+        let lexemes: Vec<&str> = tokenize(input)
+            .unwrap()
+            .iter()
+            .map(|t| t.lexeme(input))
+            .collect();
+
+        assert_eq!(
+            lexemes,
+            ["a", ":", "=", "0xFF", "+", "1_000", "+", "\"a\\tb\"", ""]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_with_errors() {
+        let input = "a := 0b12 + \"bad \\q\" + f(1e)";
+        let (tokens, errors) = tokenize_with_errors(input);
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.typ).collect();
+
+        assert_eq!(
+            types,
+            [
+                TokenType::Ident(Symbol::intern("a")),
+                TokenType::Colon,
+                TokenType::Assign,
+                TokenType::Illegal('0'),
+                TokenType::Plus,
+                TokenType::Illegal('\\'),
+                TokenType::Plus,
+                TokenType::Ident(Symbol::intern("f")),
+                TokenType::LParen,
+                TokenType::Illegal('1'),
+                TokenType::RParen,
+                TokenType::EOF,
+            ]
+        );
+
+        assert_eq!(
+            errors,
+            [
+                LexError::InvalidNumber {
+                    literal: "0b12".to_string(),
+                    span: Span::new(5, 9),
+                },
+                LexError::InvalidEscape {
+                    sequence: "\\q".to_string(),
+                    span: Span::new(17, 19),
+                },
+                LexError::InvalidNumber {
+                    literal: "1e".to_string(),
+                    span: Span::new(25, 27),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_with_errors_interpolation() {
+        let input = r#"x := "a\q{b + "{c}"} c" + 1; y"#;
+        let (tokens, errors) = tokenize_with_errors(input);
+        let types: Vec<TokenType> = tokens.into_iter().map(|t| t.typ).collect();
+
+        assert_eq!(
+            types,
+            [
+                TokenType::Ident(Symbol::intern("x")),
+                TokenType::Colon,
+                TokenType::Assign,
+                TokenType::Illegal('\\'),
+                TokenType::Plus,
+                TokenType::Int(1),
+                TokenType::Semicolon,
+                TokenType::Ident(Symbol::intern("y")),
+                TokenType::EOF,
+            ]
+        );
+
+        assert_eq!(
+            errors,
+            [LexError::InvalidEscape {
+                sequence: "\\q".to_string(),
+                span: Span::new(7, 9),
+            }]
+        );
+
+        let mut lexer = Lexer::new(r#""a\q{b + "c"#);
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::UnterminatedString {
+                span: Span::new(0, 11)
+            }
+        );
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::EOF);
+    }
+
+    #[test]
+    fn test_illegal_token_location() {
+        let input = "a := 1\n\n// comment\n  b # c";
+        let tok = Lexer::new(input)
+            .map(|t| t.unwrap())
+            .find(|t| matches!(t.typ, TokenType::Illegal(_)))
+            .unwrap();
+
+        assert_eq!(tok.typ, TokenType::Illegal('#'));
+        assert_eq!(tok.span, Span::new(23, 24));
+        assert_eq!(tok.to_string(), "illegal character `#` at line 4, column 5");
+
+        let input = "x := \"a\r\nb é\\q c\"";
+        let (tokens, _) = tokenize_with_errors(input);
+        let tok = &tokens[3];
+        assert_eq!(tok.typ, TokenType::Illegal('\\'));
+        assert_eq!(tok.span, Span::new(13, 15));
+        assert_eq!(
+            tok.to_string(),
+            "illegal character `\\` at line 2, column 4"
+        );
+    }
+
+    #[test]
+    fn test_prev_char() {
+        let mut lexer = Lexer::new("é\nb");
+        assert_eq!(lexer.prev_char(), None);
+
+        lexer.next_char();
+        assert_eq!(lexer.prev_char(), Some('é'));
+        assert_eq!((lexer.line, lexer.column), (1, 2));
+
+        lexer.next_char();
+        assert_eq!(lexer.prev_char(), Some('\n'));
+        assert_eq!((lexer.line, lexer.column), (2, 1));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut lexer = Lexer::new("a := 1");
+        assert_eq!(lexer.by_ref().count(), 5);
+
+        lexer.reset("b\n+ 2");
+        let tokens: Vec<Token> = lexer.map(|t| t.unwrap()).collect();
+        let types: Vec<&TokenType> = tokens.iter().map(|t| &t.typ).collect();
+        assert_eq!(
+            types,
+            [
+                &TokenType::Ident(Symbol::intern("b")),
+                &TokenType::Plus,
+                &TokenType::Int(2),
+                &TokenType::EOF,
+            ]
+        );
+        assert_eq!(tokens[1].span, Span::new(2, 3));
+        assert_eq!((tokens[1].line, tokens[1].column), (2, 1));
+    }
+
+    #[test]
+    fn test_identifiers_share_symbols() {
+        let tokens = tokenize("foo + bar * foo").unwrap();
+        match (&tokens[0].typ, &tokens[2].typ, &tokens[4].typ) {
+            (TokenType::Ident(a), TokenType::Ident(b), TokenType::Ident(c)) => {
+                assert_eq!(a, c);
+                assert_ne!(a, b);
+                assert_eq!(a.as_str(), "foo");
+            }
+            _ => panic!("expected three identifiers, got {:?}", tokens),
+        }
+    }
+
+    #[test]
+    fn test_strings_borrow_input() {
+        let tokens = tokenize(r#""plain" "esc\taped" r"raw" """multi""""#).unwrap();
+        let borrowed: Vec<bool> = tokens
+            .iter()
+            .filter_map(|tok| match &tok.typ {
+                TokenType::String(value) => Some(matches!(value, Cow::Borrowed(_))),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(borrowed, [true, false, true, true]);
+        assert_eq!(tokens[1].typ, TokenType::String("esc\taped".into()));
+    }
+
+    #[test]
+    fn test_large_identifier_input() {
+        let line = "alpha := beta + gamma_1 * delta(\"name\", epsilon) // note\n";
+        let input = line.repeat(10_000);
+
+        let tokens = tokenize(&input).unwrap();
+        assert_eq!(tokens.len(), 13 * 10_000 + 1);
+
+        // every repeated identifier is interned once and every string is borrowed
+        let idents: std::collections::HashSet<Symbol> = tokens
+            .iter()
+            .filter_map(|tok| match tok.typ {
+                TokenType::Ident(symbol) => Some(symbol),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(idents.len(), 5);
+        assert!(tokens.iter().all(|tok| match &tok.typ {
+            TokenType::String(value) => matches!(value, Cow::Borrowed("name")),
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn test_shebang() {
+        let input = "#!/usr/bin/env isy\na := 1 #!";
+        let (tokens, errors) = tokenize_with_errors(input);
+        let tokens: Vec<TokenType> = tokens.into_iter().map(|tok| tok.typ).collect();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            tokens,
+            [
+                TokenType::Ident(Symbol::intern("a")),
+                TokenType::Colon,
+                TokenType::Assign,
+                TokenType::Int(1),
+                TokenType::Illegal('#'),
+                TokenType::Bang,
+                TokenType::EOF,
+            ]
+        );
+
+        // only the start of the input can be a shebang
+        let input = " #!/usr/bin/env isy";
+        assert_eq!(
+            Lexer::new(input).next_token().unwrap().typ,
+            TokenType::Illegal('#')
+        );
+
+        let input = "a\n#!b";
+        let mut lexer = StreamLexer::new(Cursor::new(input.as_bytes()));
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::Illegal('#'));
+    }
+
+    #[test]
+    fn test_hash_comments() {
+        let input = "a # comment\nb";
+        let options = LexerOptions {
+            hash_comments: true,
+            ..LexerOptions::default()
+        };
+
+        let tokens: Vec<TokenType> = Lexer::with_options(input, options)
+            .map(|tok| tok.unwrap().typ)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                TokenType::Ident(Symbol::intern("a")),
+                TokenType::Ident(Symbol::intern("b")),
+                TokenType::EOF,
+            ]
+        );
+
+        let options = LexerOptions {
+            hash_comments: true,
+            keep_comments: true,
+            ..LexerOptions::default()
+        };
+        let mut lexer = Lexer::with_options(input, options);
+        lexer.next_token().unwrap();
+        assert_eq!(
+            lexer.next_token().unwrap().typ,
+            TokenType::Comment("# comment".into())
+        );
+
+        let mut lexer = Lexer::new(input);
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::Illegal('#'));
+    }
+
+    #[test]
+    fn test_lex_negative_numbers() {
+        let options = LexerOptions {
+            lex_negative_numbers: true,
+            ..LexerOptions::default()
+        };
+        let lex = |input| -> Vec<TokenType> {
+            Lexer::with_options(input, options)
+                .map(|tok| tok.unwrap().typ)
+                .collect()
+        };
+
+        let a = || TokenType::Ident(Symbol::intern("a"));
+        let f = || TokenType::Ident(Symbol::intern("f"));
+        let tests = [
+            // subtraction
+            ("a - 5", vec![a(), TokenType::Minus, TokenType::Int(5)]),
+            ("a -5", vec![a(), TokenType::Minus, TokenType::Int(5)]),
+            (
+                "(a)-5",
+                vec![
+                    TokenType::LParen,
+                    a(),
+                    TokenType::RParen,
+                    TokenType::Minus,
+                    TokenType::Int(5),
+                ],
+            ),
+            // negative literals
+            ("-5", vec![TokenType::Int(-5)]),
+            (
+                "f(-5)",
+                vec![
+                    f(),
+                    TokenType::LParen,
+                    TokenType::Int(-5),
+                    TokenType::RParen,
+                ],
+            ),
+            (
+                "[1, -2.5]",
+                vec![
+                    TokenType::LBracket,
+                    TokenType::Int(1),
+                    TokenType::Comma,
+                    TokenType::Float(-2.5),
+                    TokenType::RBracket,
+                ],
+            ),
+            (
+                "1 - -0x10",
+                vec![TokenType::Int(1), TokenType::Minus, TokenType::Int(-16)],
+            ),
+            (
+                "a = -1i8",
+                vec![
+                    a(),
+                    TokenType::Assign,
+                    TokenType::IntSuffixed(-1, NumberSuffix::I8),
+                ],
+            ),
+            // a space splits the sign from the number
+            ("- 5", vec![TokenType::Minus, TokenType::Int(5)]),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            let mut expected = expected.clone();
+            expected.push(TokenType::EOF);
+            assert_eq!(lex(input), expected, "{}", input);
+        });
+
+        let tok = Lexer::with_options("f(-0xff)", options)
+            .nth(2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tok.raw.as_deref(), Some("-0xff"));
+        assert_eq!(tok.span, Span::new(2, 7));
+
+        // ranges are checked with the sign
+        let tests = [
+            ("-128i8", TokenType::IntSuffixed(-128, NumberSuffix::I8)),
+            ("-9223372036854775808", TokenType::Int(i64::MIN)),
+            ("-0x8000000000000000", TokenType::Int(i64::MIN)),
+            ("-.5", TokenType::Float(-0.5)),
+            ("-.5e1", TokenType::Float(-5.0)),
+        ];
+        tests.iter().for_each(|(input, expected)| {
+            let tok = Lexer::with_options(input, options).next().unwrap().unwrap();
+            assert_eq!(tok.typ, *expected, "{}", input);
+            assert_eq!(tok.raw.as_deref(), Some(*input));
+        });
+
+        let tests = [("-1u8", 4), ("-129i8", 6), ("-9223372036854775809", 20)];
+        tests.iter().for_each(|(input, len)| {
+            let err = Lexer::with_options(input, options)
+                .next()
+                .unwrap()
+                .unwrap_err();
+            assert_eq!(
+                err,
+                LexError::NumberOverflow {
+                    literal: input.to_string(),
+                    span: Span::new(0, *len),
+                }
+            );
+        });
+
+        let err = Lexer::with_options("f(-0b2)", options)
+            .nth(2)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.span(), Span::new(2, 6));
+        assert_eq!(
+            lex("-.a"),
+            [TokenType::Minus, TokenType::Dot, a(), TokenType::EOF]
+        );
+
+        // disabled by default
+        assert_eq!(
+            Lexer::new("f(-5)").nth(2).unwrap().unwrap().typ,
+            TokenType::Minus
+        );
+    }
+
+    #[test]
+    fn test_keep_comments() {
+        let options = LexerOptions {
+            keep_comments: true,
+            ..LexerOptions::default()
+        };
+
+        let input = "a // line\n/* block /* nested */ */ b";
+        let mut lexer = Lexer::with_options(input, options);
+        let tests = [
+            (TokenType::Ident(Symbol::intern("a")), Span::new(0, 1)),
+            (TokenType::Comment("// line".into()), Span::new(2, 9)),
+            (
+                TokenType::Comment("/* block /* nested */ */".into()),
+                Span::new(10, 34),
+            ),
+            (TokenType::Ident(Symbol::intern("b")), Span::new(35, 36)),
+            (TokenType::EOF, Span::new(36, 36)),
+        ];
+
+        tests.iter().for_each(|(typ, span)| {
+            let tok = lexer.next_token().unwrap();
+            assert_eq!(tok.typ, *typ);
+            assert_eq!(tok.span, *span);
+        });
+
+        // the options survive a reset
+        lexer.reset("// again");
+        assert_eq!(
+            lexer.next_token().unwrap().typ,
+            TokenType::Comment("// again".into())
+        );
+    }
+
+    #[test]
+    fn test_lossless() {
+        let options = LexerOptions {
+            lossless: true,
+            ..LexerOptions::default()
+        };
+
+        let input =
+            "  // header\nx: int = 0xff /* a /* b */ */\t\n\n\ts := \"a {x + 1} b\" 'c'  \n";
+        let tokens: Vec<Token> = Lexer::with_options(input, options)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let output: String = tokens.iter().map(|tok| tok.lexeme(input)).collect();
+        assert_eq!(output, input);
+
+        assert_eq!(tokens[0].typ, TokenType::Whitespace("  ".into()));
+        assert_eq!(tokens[1].typ, TokenType::Comment("// header".into()));
+        assert_eq!(tokens[2].typ, TokenType::Whitespace("\n".into()));
+        assert_eq!((tokens[3].line, tokens[3].column), (2, 1));
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = LexerOptions {
+            max_token_len: Some(1024),
+            ..LexerOptions::default()
+        };
+
+        let input = format!("a := {} + 1", "x".repeat(10 * 1024 * 1024));
+        let mut lexer = Lexer::with_options(&input, options);
+        (0..3).for_each(|_| {
+            lexer.next_token().unwrap();
+        });
+
+        let err = lexer.next_token().unwrap_err();
+        assert_eq!(
+            err,
+            LexError::LimitExceeded {
+                limit: Limit::TokenLength(1024),
+                span: Span::new(5, input.len() - 4),
+            }
+        );
+        assert_eq!(err.to_string(), "token is longer than 1024 bytes");
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::Plus);
+
+        let options = LexerOptions {
+            max_input_len: Some(8),
+            ..LexerOptions::default()
+        };
+
+        let mut lexer = Lexer::with_options("a := 1 + 2", options);
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::LimitExceeded {
+                limit: Limit::InputSize(8),
+                span: Span::new(0, 10),
+            }
+        );
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::EOF);
+
+        lexer.reset("a := 1");
+        assert_eq!(lexer.count(), 5);
+    }
+
+    #[test]
+    fn test_tokenize_file() {
+        let path =
+            std::env::temp_dir().join(format!("isy_tokenize_file_{}.isy", std::process::id()));
+        std::fs::write(&path, "a := 1\nb := 0b2\n").unwrap();
+        let err = tokenize_file(&path).unwrap_err();
+        std::fs::write(&path, "a := 1").unwrap();
+        let tokens = tokenize_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.path, path);
+        assert!(matches!(
+            &err.error,
+            StreamError::Lex(LexError::InvalidNumber { span, .. }) if *span == Span::new(12, 15)
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!("{}: invalid number literal '0b2'", path.display())
+        );
+        assert_eq!(tokens.len(), 5);
+
+        let err = tokenize_file(&path).unwrap_err();
+        assert!(matches!(err.error, StreamError::Io(_)));
+        assert!(err.to_string().starts_with(&path.display().to_string()));
+    }
+
+    #[test]
+    fn test_stream_lexer() {
+        let input = "a := 1\ns := \"\"\"multi\nline\"\"\" /* a\ncomment */ b\n  0b2 c";
+        let mut lexer = StreamLexer::new(Cursor::new(input.as_bytes()));
+        let mut tokens = vec![];
+        let mut errors = vec![];
+        loop {
+            match lexer.next_token() {
+                Ok(tok) if tok.typ == TokenType::EOF => break,
+                Ok(tok) => tokens.push(tok),
+                Err(err) => errors.push(err.to_string()),
+            }
+        }
+
+        let expected = Lexer::new(input)
+            .filter_map(Result::ok)
+            .filter(|tok| tok.typ != TokenType::EOF)
+            .collect::<Vec<_>>();
+
+        // the plain lexer stops at `0b2`
+        assert_eq!(tokens.len(), 10);
+        assert_eq!(tokens[..9], expected[..]);
+
+        let c = tokens.last().unwrap();
+        assert_eq!(c.typ, TokenType::Ident(Symbol::intern("c")));
+        assert_eq!(c.lexeme(input), "c");
+        assert_eq!((c.line, c.column), (5, 7));
+        assert_eq!(errors, ["invalid number literal '0b2'"]);
+
+        let eof = lexer.next_token().unwrap();
+        assert_eq!(eof.typ, TokenType::EOF);
+        assert_eq!(eof.span, Span::new(input.len(), input.len()));
+    }
+
+    #[test]
+    fn test_stream_lexer_unterminated_string() {
+        let mut lexer = StreamLexer::new(Cursor::new("x\n\"abc\ndef".as_bytes()));
+        assert_eq!(
+            lexer.next_token().unwrap().typ,
+            TokenType::Ident(Symbol::intern("x"))
+        );
+        assert!(matches!(
+            lexer.next_token(),
+            Err(StreamError::Lex(LexError::UnterminatedString { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_stream_lexer_long_comment() {
+        let lines = "comment line /* nested\n".repeat(50_000) + &"*/\n".repeat(50_000);
+        let input = format!("a := \"x\" /*\n{}*/ b \"s\n{}\" c", lines, lines);
+        let mut lexer = StreamLexer::new(Cursor::new(input.as_bytes()));
+        let mut tokens = vec![];
+        loop {
+            match lexer.next_token().unwrap() {
+                tok if tok.typ == TokenType::EOF => break,
+                tok => tokens.push(tok),
+            }
+        }
+
+        let expected = Lexer::new(&input)
+            .map(Result::unwrap)
+            .filter(|tok| tok.typ != TokenType::EOF)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, expected);
+        assert_eq!(tokens.len(), 7);
+
+        let c = tokens.last().unwrap();
+        assert_eq!(c.lexeme(&input), "c");
+        assert_eq!((c.line, c.column), (200_003, 3));
+    }
+
+    #[test]
+    fn test_peekable_lexer() {
+        let mut lexer = PeekableLexer::new(Lexer::new("a + 0b2"));
+
+        let peeked = lexer.peek().unwrap().clone();
+        assert_eq!(peeked.typ, TokenType::Ident(Symbol::intern("a")));
+        assert_eq!(lexer.peek().unwrap().typ, peeked.typ);
+
+        assert_eq!(lexer.next_token().unwrap(), peeked);
+
+        assert_eq!(lexer.peek().unwrap().typ, TokenType::Plus);
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::Plus);
+
+        assert!(lexer.peek().is_err());
+        assert!(lexer.next_token().is_err());
+
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::EOF);
+        assert_eq!(lexer.next_token().unwrap().typ, TokenType::EOF);
+    }
+
+    #[test]
+    fn test_next_token_raw_string() {
+        let input = r###"r"C:\temp\new" r#"say "hi"\n"# r##"a "# b"## r"""###;
+        let tokens = [
+            TokenType::String(r"C:\temp\new".into()),
+            TokenType::String(r#"say "hi"\n"#.into()),
+            TokenType::String(r##"a "# b"##.into()),
+            TokenType::String("".into()),
             TokenType::EOF,
         ];
 
@@ -363,20 +3683,13 @@ mod test {
     }
 
     #[test]
-    fn test_next_token_float_operators() {
-        let input = "1.12 + 2.23 - 3.34 * 4.45 / 5.56 % 6.67";
-        let tokens = vec![
-            TokenType::Float(1.12),
-            TokenType::Plus,
-            TokenType::Float(2.23),
-            TokenType::Minus,
-            TokenType::Float(3.34),
-            TokenType::Asterisk,
-            TokenType::Float(4.45),
-            TokenType::Slash,
-            TokenType::Float(5.56),
-            TokenType::Module,
-            TokenType::Float(6.67),
+    fn test_next_token_raw_string_ident() {
+        let input = "r rx r#";
+        let tokens = [
+            TokenType::Ident(Symbol::intern("r")),
+            TokenType::Ident(Symbol::intern("rx")),
+            TokenType::Ident(Symbol::intern("r")),
+            TokenType::Illegal('#'),
             TokenType::EOF,
         ];
 
@@ -384,26 +3697,181 @@ mod test {
     }
 
     #[test]
-    fn test_next_token_string() {
-        let input = r#""hello!" + "bye!""#;
-        let tokens = vec![
-            TokenType::String("hello!".to_string()),
-            TokenType::Plus,
-            TokenType::String("bye!".to_string()),
+    fn test_next_token_unterminated_raw_string() {
+        let input = r##"r#"never closed""##;
+        assert_eq!(
+            Lexer::new(input).next_token().unwrap_err(),
+            LexError::UnterminatedString {
+                span: Span::new(0, 16)
+            }
+        );
+    }
+
+    #[test]
+    fn test_next_token_multiline_string() {
+        let input = "\"\"\"first \"line\"\n\tsecond\\tline\"\"\" x \"\"";
+        let tokens = [
+            TokenType::String("first \"line\"\n\tsecond\tline".into()),
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::String("".into()),
             TokenType::EOF,
         ];
 
         assert_tokens!(input, tokens);
+
+        let mut lexer = Lexer::new(input);
+        lexer.next_token().unwrap();
+        assert_eq!(lexer.next_token().unwrap().line, 2);
     }
 
     #[test]
-    fn test_next_token_string_escape() {
-        let input = r#""escape this \" please""#;
-        let tokens = vec![
-            TokenType::String(r#"escape this \" please"#.to_string()),
+    fn test_next_token_unterminated_multiline_string() {
+        let input = "\"\"\"never\nclosed\"\"";
+        assert_eq!(
+            Lexer::new(input).next_token().unwrap_err(),
+            LexError::UnterminatedString {
+                span: Span::new(0, 17)
+            }
+        );
+    }
+
+    #[test]
+    fn test_next_token_string_interpolation() {
+        let input = r#""hello {name}!""#;
+        let tokens = [
+            TokenType::StringStart("hello ".into()),
+            TokenType::Ident(Symbol::intern("name")),
+            TokenType::StringEnd("!".into()),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_string_interpolation_many() {
+        let input = r#""{a} and {f({x: "in {b}"})}" \{x\}"#;
+        let tokens = [
+            TokenType::StringStart("".into()),
+            TokenType::Ident(Symbol::intern("a")),
+            TokenType::StringMiddle(" and ".into()),
+            TokenType::Ident(Symbol::intern("f")),
+            TokenType::LParen,
+            TokenType::LBrace,
+            TokenType::Ident(Symbol::intern("x")),
+            TokenType::Colon,
+            TokenType::StringStart("in ".into()),
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::StringEnd("".into()),
+            TokenType::RBrace,
+            TokenType::RParen,
+            TokenType::StringEnd("".into()),
+            TokenType::Illegal('\\'),
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_byte_string() {
+        let input = r#"b"GET /\r\n" b"\x00\xfF\x41" b"\u{e9}" b"é" b"\u{2764}" b "#;
+        let tokens = [
+            TokenType::Bytes(b"GET /\r\n".to_vec()),
+            TokenType::Bytes(vec![0, 255, b'A']),
+            TokenType::Bytes(vec![0xe9]),
+            TokenType::Bytes(vec![0xe9]),
+            TokenType::Bytes("\u{2764}".as_bytes().to_vec()),
+            TokenType::Ident(Symbol::intern("b")),
+            TokenType::EOF,
+        ];
+
+        assert_tokens!(input, tokens);
+    }
+
+    #[test]
+    fn test_next_token_byte_string_errors() {
+        let tests = [
+            (
+                r#"b"\x4" x"#,
+                LexError::InvalidEscape {
+                    sequence: r"\x4".to_string(),
+                    span: Span::new(2, 5),
+                },
+            ),
+            (
+                r#"b"ok 😀" x"#,
+                LexError::InvalidByte {
+                    ch: '😀',
+                    span: Span::new(5, 9),
+                },
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.next_token().unwrap_err(), *expected);
+            assert_eq!(
+                lexer.next_token().unwrap().typ,
+                TokenType::Ident(Symbol::intern("x"))
+            );
+        });
+    }
+
+    #[test]
+    fn test_next_token_string_line_continuation() {
+        let input = "\"foo\\\nbar\" \"\"\"a\\\n  b\"\"\" x";
+        let mut lexer = Lexer::new(input);
+        let tests = [
+            (TokenType::String("foobar".into()), 1, 1),
+            (TokenType::String("a  b".into()), 2, 6),
+            (TokenType::Ident(Symbol::intern("x")), 3, 8),
+            (TokenType::EOF, 3, 9),
+        ];
+
+        tests.iter().for_each(|(typ, line, column)| {
+            let tok = lexer.next_token().unwrap();
+            assert_eq!(tok.typ, *typ);
+            assert_eq!((tok.line, tok.column), (*line, *column));
+        });
+    }
+
+    #[test]
+    fn test_next_token_string_escaped_brace() {
+        let input = r#""\{not} \{interpolated\}""#;
+        let tokens = [
+            TokenType::String("{not} {interpolated}".into()),
             TokenType::EOF,
         ];
 
         assert_tokens!(input, tokens);
     }
+
+    #[test]
+    fn test_next_token_unterminated_interpolation() {
+        let input = r#"a := "x {y"#;
+        let mut lexer = Lexer::new(input);
+        (0..5).for_each(|_| {
+            lexer.next_token().unwrap();
+        });
+
+        assert_eq!(
+            lexer.next_token().unwrap_err(),
+            LexError::UnterminatedString {
+                span: Span::new(5, 10)
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_tokens_to_json() {
+        let expected = concat!(
+            r#"[{"type":{"Ident":"x"},"lexeme":"x","span":{"start":0,"end":1},"line":1,"column":1},"#,
+            r#"{"type":"Assign","lexeme":"=","span":{"start":2,"end":3},"line":1,"column":3},"#,
+            r#"{"type":{"String":"hi"},"lexeme":"\"hi\"","span":{"start":4,"end":8},"line":1,"column":5},"#,
+            r#"{"type":"EOF","lexeme":"","span":{"start":8,"end":8},"line":1,"column":9}]"#,
+        );
+
+        assert_eq!(tokens_to_json(r#"x = "hi""#), expected);
+    }
 }
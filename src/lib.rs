@@ -1,4 +1,12 @@
-mod ast;
-mod lexer;
-mod parser;
-mod token;
+pub mod ast;
+pub mod diagnostic;
+pub mod evaluator;
+pub mod intern;
+pub mod lexer;
+pub mod optimizer;
+pub mod parser;
+pub mod repl;
+pub mod token;
+pub mod typecheck;
+pub mod types;
+pub mod visit;
@@ -0,0 +1,5 @@
+fn main() {
+    if let Err(err) = isy::repl::repl() {
+        eprintln!("{}", err);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::ast::{BinaryOp, Block, Expr, Stmt};
+use crate::evaluator::{eval, Value};
+
+/// Replaces the operations whose operands are all literals by their result,
+/// like `2 * 3` by `6`, leaving the parts that depend on variables untouched.
+///
+/// Operations that fail at runtime, like `1 / 0` or `1 + "a"`, are kept so
+/// the error is still reported when the program runs.
+pub fn fold_constants(expr: Expr) -> Expr {
+    match expr {
+        Expr::Prefix { op, right } => fold_operation(Expr::Prefix {
+            op,
+            right: Box::new(fold_constants(*right)),
+        }),
+        Expr::Binary { op, left, right } => {
+            let left = fold_constants(*left);
+
+            // the right side is never evaluated when these short-circuit
+            match (op, &left) {
+                (BinaryOp::And, Expr::Bool(false)) | (BinaryOp::Or, Expr::Bool(true)) => {
+                    return left
+                }
+                _ => {}
+            }
+
+            fold_operation(Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(fold_constants(*right)),
+            })
+        }
+        Expr::Call { callee, args } => Expr::Call {
+            callee: Box::new(fold_constants(*callee)),
+            args: args.into_iter().map(fold_constants).collect(),
+        },
+        Expr::Index { target, index } => Expr::Index {
+            target: Box::new(fold_constants(*target)),
+            index: Box::new(fold_constants(*index)),
+        },
+        Expr::Member { object, field } => Expr::Member {
+            object: Box::new(fold_constants(*object)),
+            field,
+        },
+        Expr::Block(block) => Expr::Block(fold_block(block)),
+        Expr::If { cond, then, else_ } => Expr::If {
+            cond: Box::new(fold_constants(*cond)),
+            then: fold_block(then),
+            else_: else_.map(|else_| Box::new(fold_constants(*else_))),
+        },
+        Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) | Expr::Str(_) | Expr::Ident(_) => expr,
+    }
+}
+
+fn fold_block(block: Block) -> Block {
+    Block {
+        stmts: block.stmts.into_iter().map(fold_stmt).collect(),
+        value: block.value.map(|value| Box::new(fold_constants(*value))),
+    }
+}
+
+fn fold_stmt(stmt: Stmt) -> Stmt {
+    match stmt {
+        Stmt::Decl {
+            name,
+            typ,
+            value,
+            constant,
+        } => Stmt::Decl {
+            name,
+            typ,
+            value: fold_constants(value),
+            constant,
+        },
+        Stmt::Assign { name, value } => Stmt::Assign {
+            name,
+            value: fold_constants(value),
+        },
+        Stmt::While { cond, body } => Stmt::While {
+            cond: fold_constants(cond),
+            body: fold_block(body),
+        },
+        Stmt::FnDecl {
+            name,
+            params,
+            return_type,
+            body,
+        } => Stmt::FnDecl {
+            name,
+            params,
+            return_type,
+            body: fold_block(body),
+        },
+        Stmt::Expr(expr) => Stmt::Expr(fold_constants(expr)),
+        Stmt::Break | Stmt::Continue => stmt,
+    }
+}
+
+// evaluates a prefix or binary operation with literal operands, its children are already folded
+fn fold_operation(expr: Expr) -> Expr {
+    let constant = match &expr {
+        Expr::Prefix { right, .. } => is_literal(right),
+        Expr::Binary { left, right, .. } => is_literal(left) && is_literal(right),
+        _ => false,
+    };
+
+    if !constant {
+        return expr;
+    }
+
+    match eval(&expr).ok().and_then(literal) {
+        Some(folded) => folded,
+        None => expr,
+    }
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) | Expr::Str(_)
+    )
+}
+
+fn literal(value: Value) -> Option<Expr> {
+    match value {
+        Value::Int(value) => Some(Expr::Int(value)),
+        // there are no literals for infinity or NaN, like the result of `1.0 / 0`
+        Value::Float(value) if value.is_finite() => Some(Expr::Float(value)),
+        Value::Bool(value) => Some(Expr::Bool(value)),
+        Value::String(value) => Some(Expr::Str(value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_expr;
+
+    fn fold(input: &str) -> String {
+        fold_constants(parse_expr(input).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        let tests = [
+            ("2 * 3", "6"),
+            ("-(1 + 2) * 4 % 5", "-2"),
+            ("true && false", "false"),
+            ("!(1 < 2) || 2.5 > 1", "true"),
+            ("0.5 + 0.25", "0.75"),
+            ("0.1 + 0.2", "0.30000000000000004"),
+            ("\"a\" + \"b\"", "\"ab\""),
+            ("x + 2 * 3", "(x + 6)"),
+            // the right side is never evaluated, even if it's not constant
+            ("false && x", "false"),
+            ("1 == 1 || f()", "true"),
+            ("f(1 + 1)[2 - 2].a", "f(2)[0].a"),
+            (
+                "{ x := 2 * 2; if x > 1 + 1 { x } else { 3 - 4 } }",
+                "{ x := 4; if (x > 2) { x } else { -1 } }",
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(fold(input), *expected, "{}", input);
+        });
+    }
+
+    #[test]
+    fn test_fold_constants_keeps_runtime_errors() {
+        let tests = [
+            ("1 + 2 * x", "(1 + (2 * x))"),
+            ("1 / 0", "(1 / 0)"),
+            ("(1 + 2) % (3 - 3)", "(3 % 0)"),
+            ("1 + \"a\"", "(1 + \"a\")"),
+            ("-true", "(-true)"),
+            ("true && 1", "(true && 1)"),
+            ("1.0 / 0", "(1.0 / 0)"),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(fold(input), *expected, "{}", input);
+        });
+    }
+}
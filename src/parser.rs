@@ -1 +1,1261 @@
+use crate::ast::{BinaryOp, Block, Expr, Param, PrefixOp, Stmt};
+use crate::lexer::{tokenize, tokenize_with_errors, LexError};
+use crate::token::{Span, Token, TokenKind, TokenType};
+use std::fmt;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    Lex(LexError),
+    /// also reported for char literals, which are lexed but have no value yet
+    UnexpectedToken {
+        found: Token<'static>,
+    },
+    Expected {
+        expected: TokenKind,
+        found: Token<'static>,
+    },
+    /// the input ended in the middle of an expression or statement
+    UnexpectedEof {
+        span: Span,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl ParseError {
+    /// Part of the input where the error was found.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::Lex(err) => err.span(),
+            ParseError::UnexpectedToken { found } | ParseError::Expected { found, .. } => {
+                found.span
+            }
+            ParseError::UnexpectedEof { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Lex(err) => write!(f, "{}", err),
+            ParseError::UnexpectedToken { found } => write!(f, "unexpected {}", found),
+            ParseError::Expected { expected, found } => {
+                write!(f, "expected `{}` but found {}", expected, found)
+            }
+            ParseError::UnexpectedEof { line, column, .. } => {
+                write!(f, "unexpected `EOF` at line {}, column {}", line, column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<LexError> for ParseError {
+    fn from(err: LexError) -> ParseError {
+        ParseError::Lex(err)
+    }
+}
+
+pub struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser over `tokens`, like the ones returned by `tokenize`.
+    /// If they don't end with `EOF` one is added right after the last token. The source
+    /// is not at hand, so its column is only exact when the last token fits in one line
+    /// and, identifiers aside, is ascii text.
+    pub fn new(mut tokens: Vec<Token<'a>>) -> Parser<'a> {
+        if tokens.last().is_none_or(|tok| tok.typ != TokenType::EOF) {
+            let (end, line, column) = tokens.last().map_or((0, 1, 1), |tok| {
+                let width = match &tok.typ {
+                    TokenType::Ident(name) => name.as_str().chars().count(),
+                    _ => tok.span.end - tok.span.start,
+                };
+                (tok.span.end, tok.line, tok.column + width)
+            });
+            tokens.push(Token::new(
+                TokenType::EOF,
+                Span::new(end, end),
+                line,
+                column,
+            ));
+        }
+
+        Parser { tokens, pos: 0 }
+    }
+
+    /// Parses statements until `EOF`, they can be optionally separated by `;`.
+    pub fn parse_statements(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut stmts = vec![];
+        while self.cur().typ != TokenType::EOF {
+            stmts.push(self.parse_statement()?);
+            self.consume_if(TokenKind::Semicolon);
+        }
+
+        Ok(stmts)
+    }
+
+    /// Parses statements until `EOF` without stopping on errors. A statement that
+    /// fails is skipped up to the next `;` or the end of the line where it failed,
+    /// and parsing resumes from there.
+    pub fn parse_statements_with_errors(&mut self) -> (Vec<Stmt>, Vec<ParseError>) {
+        let mut stmts = vec![];
+        let mut errors = vec![];
+        while self.cur().typ != TokenType::EOF {
+            let start = self.pos;
+            match self.parse_statement() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(err) => {
+                    errors.push(err);
+                    self.recover(start);
+                }
+            }
+
+            self.consume_if(TokenKind::Semicolon);
+        }
+
+        (stmts, errors)
+    }
+
+    // skips the rest of the statement that started at `start`, stopping before
+    // the next `;` or the first token of a new line
+    fn recover(&mut self, start: usize) {
+        if self.pos == start {
+            self.next_token();
+        }
+
+        // the statement failed on its own `;`
+        let last = &self.tokens[self.pos - 1];
+        if last.typ == TokenType::Semicolon {
+            return;
+        }
+
+        let line = last.line;
+        while self.cur().line == line
+            && !matches!(self.cur().typ, TokenType::Semicolon | TokenType::EOF)
+        {
+            self.next_token();
+        }
+    }
+
+    pub fn parse_statement(&mut self) -> Result<Stmt, ParseError> {
+        match (&self.cur().typ, &self.peek().typ) {
+            (TokenType::Let, _) | (TokenType::Const, _) => {
+                let constant = self.next_token().typ == TokenType::Const;
+                self.parse_declaration(constant)
+            }
+            (TokenType::Ident(_), TokenType::Colon) => self.parse_declaration(false),
+            (TokenType::Ident(name), TokenType::Assign) => {
+                let name = name.as_str().to_string();
+                self.next_token();
+                self.next_token();
+                let value = self.parse_expression()?;
+                Ok(Stmt::Assign { name, value })
+            }
+            (TokenType::While, _) => {
+                self.next_token();
+                let cond = self.parse_expression()?;
+                self.expect(TokenKind::LBrace)?;
+                let body = self.parse_block()?;
+                Ok(Stmt::While { cond, body })
+            }
+            (TokenType::Fn, _) => {
+                self.next_token();
+                self.parse_fn()
+            }
+            (TokenType::Break, _) => {
+                self.next_token();
+                Ok(Stmt::Break)
+            }
+            (TokenType::Continue, _) => {
+                self.next_token();
+                Ok(Stmt::Continue)
+            }
+            _ => Ok(Stmt::Expr(self.parse_expression()?)),
+        }
+    }
+
+    // `name := value` or `name: type = value`
+    fn parse_declaration(&mut self, constant: bool) -> Result<Stmt, ParseError> {
+        let name = self.parse_ident()?;
+        self.expect(TokenKind::Colon)?;
+        let typ = match self.cur().typ.clone() {
+            TokenType::Type(typ) => {
+                self.next_token();
+                Some(typ.into_owned())
+            }
+            _ => None,
+        };
+
+        self.expect(TokenKind::Assign)?;
+        let value = self.parse_expression()?;
+        Ok(Stmt::Decl {
+            name,
+            typ,
+            value,
+            constant,
+        })
+    }
+
+    // `name(a: int, b) -> int { ... }` with the `fn` already consumed, parameter
+    // and return types are optional and a trailing comma is allowed
+    fn parse_fn(&mut self) -> Result<Stmt, ParseError> {
+        let name = self.parse_ident()?;
+        self.expect(TokenKind::LParen)?;
+
+        let mut params = vec![];
+        while self.cur().typ != TokenType::RParen {
+            let name = self.parse_ident()?;
+            let typ = if self.consume_if(TokenKind::Colon) {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+
+            params.push(Param { name, typ });
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RParen)?;
+        let return_type = if self.consume_if(TokenKind::Arrow) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        self.expect(TokenKind::LBrace)?;
+        let body = self.parse_block()?;
+        Ok(Stmt::FnDecl {
+            name,
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    fn parse_ident(&mut self) -> Result<String, ParseError> {
+        let tok = self.next_token();
+        match tok.typ {
+            TokenType::Ident(name) => Ok(name.as_str().to_string()),
+            _ => Err(unexpected(tok)),
+        }
+    }
+
+    fn parse_type(&mut self) -> Result<String, ParseError> {
+        let tok = self.next_token();
+        match tok.typ {
+            TokenType::Type(typ) => Ok(typ.into_owned()),
+            _ => Err(unexpected(tok)),
+        }
+    }
+
+    pub fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        self.parse_expression_with(Precedence::Lowest)
+    }
+
+    // pratt parser, keeps folding infix operators while they bind tighter than `prec`
+    fn parse_expression_with(&mut self, prec: Precedence) -> Result<Expr, ParseError> {
+        let mut left = self.parse_prefix()?;
+
+        while precedence(&self.cur().typ) > prec {
+            let tok = self.next_token();
+            left = self.parse_infix(left, tok)?;
+        }
+
+        Ok(left)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expr, ParseError> {
+        let tok = self.next_token();
+        let expr = match tok.typ {
+            // the lexer already checked that suffixed values fit their type
+            TokenType::Int(value) | TokenType::IntSuffixed(value, _) => Expr::Int(value),
+            TokenType::Float(value) | TokenType::FloatSuffixed(value, _) => Expr::Float(value),
+            TokenType::Bool(value) => Expr::Bool(value),
+            TokenType::String(value) => Expr::Str(value.into_owned()),
+            TokenType::Ident(name) => Expr::Ident(name.as_str().to_string()),
+            TokenType::Minus => self.parse_prefix_op(PrefixOp::Neg)?,
+            TokenType::Bang => self.parse_prefix_op(PrefixOp::Not)?,
+            TokenType::LParen => {
+                let expr = self.parse_expression()?;
+                self.expect(TokenKind::RParen)?;
+                expr
+            }
+            TokenType::LBrace => Expr::Block(self.parse_block()?),
+            TokenType::If => self.parse_if()?,
+            // there are no char values yet, char literals are only lexed
+            TokenType::Char(_) => return Err(unexpected(tok)),
+            _ => return Err(unexpected(tok)),
+        };
+
+        Ok(expr)
+    }
+
+    fn parse_prefix_op(&mut self, op: PrefixOp) -> Result<Expr, ParseError> {
+        let right = self.parse_expression_with(Precedence::Prefix)?;
+        Ok(Expr::Prefix {
+            op,
+            right: Box::new(right),
+        })
+    }
+
+    fn parse_infix(&mut self, left: Expr, tok: Token<'a>) -> Result<Expr, ParseError> {
+        let op = match tok.typ {
+            TokenType::LParen => return self.parse_call(left),
+            TokenType::LBracket => {
+                let index = self.parse_expression()?;
+                self.expect(TokenKind::RBracket)?;
+                return Ok(Expr::Index {
+                    target: Box::new(left),
+                    index: Box::new(index),
+                });
+            }
+            TokenType::Dot => {
+                let tok = self.next_token();
+                let field = match tok.typ {
+                    TokenType::Ident(field) => field.as_str().to_string(),
+                    _ => return Err(unexpected(tok)),
+                };
+
+                return Ok(Expr::Member {
+                    object: Box::new(left),
+                    field,
+                });
+            }
+            TokenType::Plus => BinaryOp::Add,
+            TokenType::Minus => BinaryOp::Sub,
+            TokenType::Asterisk => BinaryOp::Mul,
+            TokenType::Slash => BinaryOp::Div,
+            TokenType::Percent => BinaryOp::Rem,
+            TokenType::Equal => BinaryOp::Eq,
+            TokenType::NotEqual => BinaryOp::NotEq,
+            TokenType::Lt => BinaryOp::Lt,
+            TokenType::Gt => BinaryOp::Gt,
+            TokenType::Lte => BinaryOp::Lte,
+            TokenType::Gte => BinaryOp::Gte,
+            TokenType::And => BinaryOp::And,
+            TokenType::Or => BinaryOp::Or,
+            TokenType::BitAnd => BinaryOp::BitAnd,
+            TokenType::BitOr => BinaryOp::BitOr,
+            TokenType::BitXor => BinaryOp::BitXor,
+            TokenType::Shl => BinaryOp::Shl,
+            TokenType::Shr => BinaryOp::Shr,
+            _ => return Err(unexpected(tok)),
+        };
+
+        let right = self.parse_expression_with(precedence(&tok.typ))?;
+        Ok(Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    // `{ stmts }` with the `{` already consumed, statements are separated like in
+    // `parse_statements` and a final expression without `;` is the value of the block
+    fn parse_block(&mut self) -> Result<Block, ParseError> {
+        let mut stmts = vec![];
+        let mut value = None;
+        while self.cur().typ != TokenType::RBrace && self.cur().typ != TokenType::EOF {
+            let stmt = self.parse_statement()?;
+            match (stmt, &self.cur().typ) {
+                (stmt, TokenType::Semicolon) => {
+                    self.next_token();
+                    stmts.push(stmt);
+                }
+                (Stmt::Expr(expr), TokenType::RBrace) => value = Some(Box::new(expr)),
+                (stmt, _) => stmts.push(stmt),
+            }
+        }
+
+        self.expect(TokenKind::RBrace)?;
+        Ok(Block { stmts, value })
+    }
+
+    // `if cond { ... }` with the `if` already consumed, optionally followed
+    // by `else { ... }` or `else if ...`
+    fn parse_if(&mut self) -> Result<Expr, ParseError> {
+        let cond = self.parse_expression()?;
+        self.expect(TokenKind::LBrace)?;
+        let then = self.parse_block()?;
+
+        let else_ = if self.consume_if(TokenKind::Else) {
+            let else_ = match self.next_token() {
+                tok if tok.typ == TokenType::If => self.parse_if()?,
+                tok if tok.typ == TokenType::LBrace => Expr::Block(self.parse_block()?),
+                tok => return Err(unexpected(tok)),
+            };
+
+            Some(Box::new(else_))
+        } else {
+            None
+        };
+
+        Ok(Expr::If {
+            cond: Box::new(cond),
+            then,
+            else_,
+        })
+    }
+
+    // `callee(a, b)` with the `(` already consumed, a trailing comma is allowed
+    fn parse_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut args = vec![];
+        while self.cur().typ != TokenType::RParen {
+            args.push(self.parse_expression()?);
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+
+        self.expect(TokenKind::RParen)?;
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            args,
+        })
+    }
+
+    fn cur(&self) -> &Token<'a> {
+        let last = self.tokens.len() - 1;
+        &self.tokens[self.pos.min(last)]
+    }
+
+    fn peek(&self) -> &Token<'a> {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + 1).min(last)]
+    }
+
+    // returns the current token and moves to the next one, `EOF` is never consumed
+    fn next_token(&mut self) -> Token<'a> {
+        let tok = self.cur().clone();
+        if tok.typ != TokenType::EOF {
+            self.pos += 1;
+        }
+
+        tok
+    }
+
+    /// Consumes the current token if it's of the given `kind`,
+    /// otherwise fails naming what was expected and what was found.
+    pub fn expect(&mut self, kind: TokenKind) -> Result<Token<'a>, ParseError> {
+        if self.cur().typ.kind() != kind {
+            return Err(ParseError::Expected {
+                expected: kind,
+                found: self.cur().clone().into_owned(),
+            });
+        }
+
+        Ok(self.next_token())
+    }
+
+    /// Consumes the current token if it's of the given `kind`, returning whether it did.
+    pub fn consume_if(&mut self, kind: TokenKind) -> bool {
+        let found = self.cur().typ.kind() == kind;
+        if found {
+            self.next_token();
+        }
+
+        found
+    }
+
+    fn expect_eof(&mut self) -> Result<(), ParseError> {
+        match self.cur().typ {
+            TokenType::EOF => Ok(()),
+            _ => Err(unexpected(self.cur().clone())),
+        }
+    }
+}
+
+// errors own their token so they don't borrow the input
+fn unexpected(tok: Token) -> ParseError {
+    match tok.typ {
+        TokenType::EOF => ParseError::UnexpectedEof {
+            span: tok.span,
+            line: tok.line,
+            column: tok.column,
+        },
+        _ => ParseError::UnexpectedToken {
+            found: tok.into_owned(),
+        },
+    }
+}
+
+/// Parses the whole `input` as a single expression.
+pub fn parse_expr(input: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(tokenize(input)?);
+    let expr = parser.parse_expression()?;
+    parser.expect_eof()?;
+    Ok(expr)
+}
+
+/// Parses the whole `input` as a list of statements.
+pub fn parse_program(input: &str) -> Result<Vec<Stmt>, ParseError> {
+    Parser::new(tokenize(input)?).parse_statements()
+}
+
+/// Parses the whole `input` as a list of statements without stopping on errors,
+/// returning every statement that could be parsed along with the lexing and
+/// parsing errors found, sorted by their position.
+pub fn parse_program_with_errors(input: &str) -> (Vec<Stmt>, Vec<ParseError>) {
+    let (tokens, lex_errors) = tokenize_with_errors(input);
+    let (stmts, parse_errors) = Parser::new(tokens).parse_statements_with_errors();
+
+    // `Illegal` tokens come from lexing errors, which are already reported
+    let mut errors: Vec<ParseError> = lex_errors.into_iter().map(ParseError::Lex).collect();
+    errors.extend(parse_errors.into_iter().filter(|err| {
+        !matches!(
+            err,
+            ParseError::UnexpectedToken { found } | ParseError::Expected { found, .. }
+                if matches!(found.typ, TokenType::Illegal(_))
+        )
+    }));
+    errors.sort_by_key(|err| err.span().start);
+
+    (stmts, errors)
+}
+
+/// How tightly an infix operator binds its operands, from loosest to tightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Lowest,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
+    Sum,
+    Product,
+    Prefix,
+    /// postfix calls `f(x)`, indexing `a[i]` and member access `a.b`
+    Call,
+}
+
+/// Returns the precedence of `typ` used as an infix operator,
+/// `Lowest` if it's not an operator.
+pub fn precedence(typ: &TokenType) -> Precedence {
+    match typ {
+        TokenType::Or => Precedence::Or,
+        TokenType::And => Precedence::And,
+        TokenType::Equal | TokenType::NotEqual => Precedence::Equality,
+        TokenType::Lt | TokenType::Gt | TokenType::Lte | TokenType::Gte => Precedence::Comparison,
+        TokenType::BitOr => Precedence::BitOr,
+        TokenType::BitXor => Precedence::BitXor,
+        TokenType::BitAnd => Precedence::BitAnd,
+        TokenType::Shl | TokenType::Shr => Precedence::Shift,
+        TokenType::Plus | TokenType::Minus => Precedence::Sum,
+        TokenType::Asterisk | TokenType::Slash | TokenType::Percent => Precedence::Product,
+        TokenType::LParen | TokenType::LBracket | TokenType::Dot => Precedence::Call,
+        _ => Precedence::Lowest,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intern::Symbol;
+
+    fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+        Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    #[test]
+    fn test_parse_literals() {
+        let tests = [
+            ("10", Expr::Int(10)),
+            ("2.5", Expr::Float(2.5)),
+            ("255u8", Expr::Int(255)),
+            ("1.5f32", Expr::Float(1.5)),
+            ("true", Expr::Bool(true)),
+            ("\"hello\"", Expr::Str("hello".to_string())),
+            ("my_var", Expr::Ident("my_var".to_string())),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(parse_expr(input).unwrap(), *expected, "{}", input);
+        });
+    }
+
+    #[test]
+    fn test_parse_binary_precedence() {
+        let expr = parse_expr("1 + 2 * 3").unwrap();
+        let expected = binary(
+            BinaryOp::Add,
+            Expr::Int(1),
+            binary(BinaryOp::Mul, Expr::Int(2), Expr::Int(3)),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_suffixed_numbers() {
+        let expr = parse_expr("255u8 + 1").unwrap();
+        assert_eq!(expr, binary(BinaryOp::Add, Expr::Int(255), Expr::Int(1)));
+        assert!(matches!(parse_expr("256u8"), Err(ParseError::Lex(_))));
+    }
+
+    #[test]
+    fn test_parse_binary_left_associative() {
+        let expr = parse_expr("a - b - c % 2").unwrap();
+        let expected = binary(
+            BinaryOp::Sub,
+            binary(
+                BinaryOp::Sub,
+                Expr::Ident("a".to_string()),
+                Expr::Ident("b".to_string()),
+            ),
+            binary(BinaryOp::Rem, Expr::Ident("c".to_string()), Expr::Int(2)),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_unexpected_token() {
+        let err = parse_expr("1 + * 2").unwrap_err();
+        match err {
+            ParseError::UnexpectedToken { found } => assert_eq!(found.typ, TokenType::Asterisk),
+            _ => panic!("Unexpected error {:?}", err),
+        }
+
+        assert!(matches!(
+            parse_expr("1 2"),
+            Err(ParseError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(parse_expr("1 + 0b2"), Err(ParseError::Lex(_))));
+        assert!(matches!(
+            parse_expr("'a'"),
+            Err(ParseError::UnexpectedToken { found }) if found.typ == TokenType::Char('a')
+        ));
+        assert_eq!(
+            parse_expr("1 +"),
+            Err(ParseError::UnexpectedEof {
+                span: Span::new(3, 3),
+                line: 1,
+                column: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_comparison_precedence() {
+        let expr = parse_expr("2 + 3 * 4 == 14").unwrap();
+        let expected = binary(
+            BinaryOp::Eq,
+            binary(
+                BinaryOp::Add,
+                Expr::Int(2),
+                binary(BinaryOp::Mul, Expr::Int(3), Expr::Int(4)),
+            ),
+            Expr::Int(14),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_logical_precedence() {
+        let expr = parse_expr("a || b && c < 1").unwrap();
+        let expected = binary(
+            BinaryOp::Or,
+            Expr::Ident("a".to_string()),
+            binary(
+                BinaryOp::And,
+                Expr::Ident("b".to_string()),
+                binary(BinaryOp::Lt, Expr::Ident("c".to_string()), Expr::Int(1)),
+            ),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_bitwise_precedence() {
+        let expr = parse_expr("a | b ^ c & d << 1").unwrap();
+        let expected = binary(
+            BinaryOp::BitOr,
+            Expr::Ident("a".to_string()),
+            binary(
+                BinaryOp::BitXor,
+                Expr::Ident("b".to_string()),
+                binary(
+                    BinaryOp::BitAnd,
+                    Expr::Ident("c".to_string()),
+                    binary(BinaryOp::Shl, Expr::Ident("d".to_string()), Expr::Int(1)),
+                ),
+            ),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_precedence_order() {
+        assert!(precedence(&TokenType::Asterisk) > precedence(&TokenType::Plus));
+        assert!(precedence(&TokenType::Plus) > precedence(&TokenType::Lt));
+        assert!(precedence(&TokenType::Lt) > precedence(&TokenType::Equal));
+        assert!(precedence(&TokenType::And) > precedence(&TokenType::Or));
+        assert_eq!(precedence(&TokenType::Comma), Precedence::Lowest);
+    }
+
+    fn prefix(op: PrefixOp, right: Expr) -> Expr {
+        Expr::Prefix {
+            op,
+            right: Box::new(right),
+        }
+    }
+
+    #[test]
+    fn test_parse_negation() {
+        let expr = parse_expr("-1 + 2").unwrap();
+        let expected = binary(
+            BinaryOp::Add,
+            prefix(PrefixOp::Neg, Expr::Int(1)),
+            Expr::Int(2),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_logical_not() {
+        let expr = parse_expr("!done").unwrap();
+        assert_eq!(expr, prefix(PrefixOp::Not, Expr::Ident("done".to_string())));
+
+        let expr = parse_expr("!-a").unwrap();
+        let expected = prefix(
+            PrefixOp::Not,
+            prefix(PrefixOp::Neg, Expr::Ident("a".to_string())),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_grouping() {
+        let expr = parse_expr("(1 + 2) * 3").unwrap();
+        let expected = binary(
+            BinaryOp::Mul,
+            binary(BinaryOp::Add, Expr::Int(1), Expr::Int(2)),
+            Expr::Int(3),
+        );
+
+        assert_eq!(expr, expected);
+
+        let expr = parse_expr("-((a))").unwrap();
+        assert_eq!(expr, prefix(PrefixOp::Neg, Expr::Ident("a".to_string())));
+
+        let expr = parse_expr("2 * (1 - (3 - 4))").unwrap();
+        let expected = binary(
+            BinaryOp::Mul,
+            Expr::Int(2),
+            binary(
+                BinaryOp::Sub,
+                Expr::Int(1),
+                binary(BinaryOp::Sub, Expr::Int(3), Expr::Int(4)),
+            ),
+        );
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_parse_unbalanced_paren() {
+        let err = parse_expr("(1 + 2").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::Expected {
+                expected: TokenKind::RParen,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "expected `)` but found `EOF` at line 1, column 7"
+        );
+
+        let err = parse_expr("1 + 2)").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected `)` at line 1, column 6");
+    }
+
+    fn call(callee: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call {
+            callee: Box::new(Expr::Ident(callee.to_string())),
+            args,
+        }
+    }
+
+    #[test]
+    fn test_parse_call() {
+        let tests = [
+            ("f()", call("f", vec![])),
+            ("f(1)", call("f", vec![Expr::Int(1)])),
+            (
+                "add(1, x * 2)",
+                call(
+                    "add",
+                    vec![
+                        Expr::Int(1),
+                        binary(BinaryOp::Mul, Expr::Ident("x".to_string()), Expr::Int(2)),
+                    ],
+                ),
+            ),
+            (
+                "f(a, b,)",
+                call(
+                    "f",
+                    vec![Expr::Ident("a".to_string()), Expr::Ident("b".to_string())],
+                ),
+            ),
+            (
+                "f(g(x))",
+                call("f", vec![call("g", vec![Expr::Ident("x".to_string())])]),
+            ),
+            // calls bind tighter than prefix operators
+            (
+                "-f(x)",
+                prefix(PrefixOp::Neg, call("f", vec![Expr::Ident("x".to_string())])),
+            ),
+            (
+                "f(1)(2)",
+                Expr::Call {
+                    callee: Box::new(call("f", vec![Expr::Int(1)])),
+                    args: vec![Expr::Int(2)],
+                },
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(parse_expr(input).unwrap(), *expected, "{}", input);
+        });
+    }
+
+    #[test]
+    fn test_parse_call_errors() {
+        let err = parse_expr("f(1 2)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `)` but found `2` at line 1, column 5"
+        );
+
+        let err = parse_expr("f(,)").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected `,` at line 1, column 3");
+
+        let err = parse_expr("f(a,").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected `EOF` at line 1, column 5");
+    }
+
+    fn index(target: Expr, index: Expr) -> Expr {
+        Expr::Index {
+            target: Box::new(target),
+            index: Box::new(index),
+        }
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let tests = [
+            ("a[0]", index(Expr::Ident("a".to_string()), Expr::Int(0))),
+            (
+                "matrix[i][j + 1]",
+                index(
+                    index(
+                        Expr::Ident("matrix".to_string()),
+                        Expr::Ident("i".to_string()),
+                    ),
+                    binary(BinaryOp::Add, Expr::Ident("j".to_string()), Expr::Int(1)),
+                ),
+            ),
+            (
+                "f(x)[0] * 2",
+                binary(
+                    BinaryOp::Mul,
+                    index(call("f", vec![Expr::Ident("x".to_string())]), Expr::Int(0)),
+                    Expr::Int(2),
+                ),
+            ),
+            (
+                "a[b[0]]",
+                index(
+                    Expr::Ident("a".to_string()),
+                    index(Expr::Ident("b".to_string()), Expr::Int(0)),
+                ),
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(parse_expr(input).unwrap(), *expected, "{}", input);
+        });
+
+        let err = parse_expr("a[0").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `]` but found `EOF` at line 1, column 4"
+        );
+    }
+
+    fn member(object: Expr, field: &str) -> Expr {
+        Expr::Member {
+            object: Box::new(object),
+            field: field.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_member() {
+        let tests = [
+            (
+                "foo.bar.baz",
+                member(member(Expr::Ident("foo".to_string()), "bar"), "baz"),
+            ),
+            (
+                "obj.method(x)",
+                Expr::Call {
+                    callee: Box::new(member(Expr::Ident("obj".to_string()), "method")),
+                    args: vec![Expr::Ident("x".to_string())],
+                },
+            ),
+            (
+                "!a.items[0].done",
+                prefix(
+                    PrefixOp::Not,
+                    member(
+                        index(member(Expr::Ident("a".to_string()), "items"), Expr::Int(0)),
+                        "done",
+                    ),
+                ),
+            ),
+            // the lexer reads these dots as part of the number
+            ("1.5", Expr::Float(1.5)),
+            (
+                "x * .5",
+                binary(
+                    BinaryOp::Mul,
+                    Expr::Ident("x".to_string()),
+                    Expr::Float(0.5),
+                ),
+            ),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(parse_expr(input).unwrap(), *expected, "{}", input);
+        });
+
+        let err = parse_expr("a.0").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected `0` at line 1, column 3");
+    }
+
+    #[test]
+    fn test_parse_block() {
+        let expr = parse_expr("{}").unwrap();
+        assert_eq!(
+            expr,
+            Expr::Block(Block {
+                stmts: vec![],
+                value: None,
+            })
+        );
+
+        let expr = parse_expr("{ a := 1; b := 2; a + b }").unwrap();
+        let decl = |name: &str, value: i64| Stmt::Decl {
+            name: name.to_string(),
+            typ: None,
+            value: Expr::Int(value),
+            constant: false,
+        };
+        let expected = Expr::Block(Block {
+            stmts: vec![decl("a", 1), decl("b", 2)],
+            value: Some(Box::new(binary(
+                BinaryOp::Add,
+                Expr::Ident("a".to_string()),
+                Expr::Ident("b".to_string()),
+            ))),
+        });
+        assert_eq!(expr, expected);
+
+        // a `;` after the last expression discards its value
+        let expr = parse_expr("{\n  a := 1\n  f(a);\n}").unwrap();
+        let expected = Expr::Block(Block {
+            stmts: vec![
+                decl("a", 1),
+                Stmt::Expr(call("f", vec![Expr::Ident("a".to_string())])),
+            ],
+            value: None,
+        });
+        assert_eq!(expr, expected);
+
+        let err = parse_expr("{ a := 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `}` but found `EOF` at line 1, column 9"
+        );
+    }
+
+    fn block(value: Expr) -> Block {
+        Block {
+            stmts: vec![],
+            value: Some(Box::new(value)),
+        }
+    }
+
+    #[test]
+    fn test_parse_if() {
+        let expr = parse_expr("if x < 1 { 1 }").unwrap();
+        let expected = Expr::If {
+            cond: Box::new(binary(
+                BinaryOp::Lt,
+                Expr::Ident("x".to_string()),
+                Expr::Int(1),
+            )),
+            then: block(Expr::Int(1)),
+            else_: None,
+        };
+        assert_eq!(expr, expected);
+
+        let expr = parse_expr("if a { 1 } else if b { 2 } else { 3 }").unwrap();
+        let expected = Expr::If {
+            cond: Box::new(Expr::Ident("a".to_string())),
+            then: block(Expr::Int(1)),
+            else_: Some(Box::new(Expr::If {
+                cond: Box::new(Expr::Ident("b".to_string())),
+                then: block(Expr::Int(2)),
+                else_: Some(Box::new(Expr::Block(block(Expr::Int(3))))),
+            })),
+        };
+        assert_eq!(expr, expected);
+
+        let err = parse_expr("if a 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `{` but found `1` at line 1, column 6"
+        );
+
+        let err = parse_expr("if a {} else 1").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected `1` at line 1, column 14");
+    }
+
+    #[test]
+    fn test_parse_while() {
+        let stmts =
+            parse_program("while i < 3 { i = i + 1; if i == 2 { continue } break }").unwrap();
+        let expected = [Stmt::While {
+            cond: binary(BinaryOp::Lt, Expr::Ident("i".to_string()), Expr::Int(3)),
+            body: Block {
+                stmts: vec![
+                    Stmt::Assign {
+                        name: "i".to_string(),
+                        value: binary(BinaryOp::Add, Expr::Ident("i".to_string()), Expr::Int(1)),
+                    },
+                    Stmt::Expr(Expr::If {
+                        cond: Box::new(binary(
+                            BinaryOp::Eq,
+                            Expr::Ident("i".to_string()),
+                            Expr::Int(2),
+                        )),
+                        then: Block {
+                            stmts: vec![Stmt::Continue],
+                            value: None,
+                        },
+                        else_: None,
+                    }),
+                    Stmt::Break,
+                ],
+                value: None,
+            },
+        }];
+
+        assert_eq!(stmts, expected);
+
+        let err = parse_program("while true 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `{` but found `1` at line 1, column 12"
+        );
+    }
+
+    #[test]
+    fn test_parse_fn() {
+        let stmts = parse_program("fn answer() { 42 }").unwrap();
+        let expected = [Stmt::FnDecl {
+            name: "answer".to_string(),
+            params: vec![],
+            return_type: None,
+            body: block(Expr::Int(42)),
+        }];
+        assert_eq!(stmts, expected);
+
+        let stmts = parse_program("fn add(a: int, b,) -> int {\n  a + b\n}").unwrap();
+        let expected = [Stmt::FnDecl {
+            name: "add".to_string(),
+            params: vec![
+                Param {
+                    name: "a".to_string(),
+                    typ: Some("int".to_string()),
+                },
+                Param {
+                    name: "b".to_string(),
+                    typ: None,
+                },
+            ],
+            return_type: Some("int".to_string()),
+            body: block(binary(
+                BinaryOp::Add,
+                Expr::Ident("a".to_string()),
+                Expr::Ident("b".to_string()),
+            )),
+        }];
+        assert_eq!(stmts, expected);
+
+        let tests = [
+            ("fn (a) {}", "unexpected `(` at line 1, column 4"),
+            (
+                "fn f(a b) {}",
+                "expected `)` but found `b` at line 1, column 8",
+            ),
+            ("fn f(a: 1) {}", "unexpected `1` at line 1, column 9"),
+            ("fn f() -> {}", "unexpected `{` at line 1, column 11"),
+            ("fn f() 1", "expected `{` but found `1` at line 1, column 8"),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            assert_eq!(parse_program(input).unwrap_err().to_string(), *expected);
+        });
+    }
+
+    #[test]
+    fn test_parse_declarations() {
+        let stmts = parse_program("x := 5; let y: int = x + 1\nconst PI: float = 2.5 x").unwrap();
+        let expected = [
+            Stmt::Decl {
+                name: "x".to_string(),
+                typ: None,
+                value: Expr::Int(5),
+                constant: false,
+            },
+            Stmt::Decl {
+                name: "y".to_string(),
+                typ: Some("int".to_string()),
+                value: binary(BinaryOp::Add, Expr::Ident("x".to_string()), Expr::Int(1)),
+                constant: false,
+            },
+            Stmt::Decl {
+                name: "PI".to_string(),
+                typ: Some("float".to_string()),
+                value: Expr::Float(2.5),
+                constant: true,
+            },
+            Stmt::Expr(Expr::Ident("x".to_string())),
+        ];
+
+        assert_eq!(stmts, expected);
+    }
+
+    #[test]
+    fn test_parse_declaration_missing_assign() {
+        let err = parse_program("x: int 5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `=` but found `5` at line 1, column 8"
+        );
+    }
+
+    #[test]
+    fn test_parse_program_with_errors() {
+        let input = "a := 1\nb := * 2\nc := (1 + 2\nd := 4; e: 5; f := 6";
+        let (stmts, errors) = parse_program_with_errors(input);
+        let names: Vec<_> = stmts
+            .iter()
+            .map(|stmt| match stmt {
+                Stmt::Decl { name, .. } => name.as_str(),
+                _ => panic!("Unexpected statement {:?}", stmt),
+            })
+            .collect();
+        let errors: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+
+        assert_eq!(names, ["a", "d", "f"]);
+        assert_eq!(
+            errors,
+            [
+                "unexpected `*` at line 2, column 6",
+                "expected `)` but found `d` at line 4, column 1",
+                "expected `=` but found `5` at line 4, column 12",
+            ]
+        );
+
+        // lexing errors are reported once, in order with the parsing ones
+        let (stmts, errors) = parse_program_with_errors("x := 0b2 +\ny := (;\nz := 1 +");
+        assert!(stmts.is_empty());
+        assert!(matches!(errors[0], ParseError::Lex(_)));
+        assert_eq!(errors[1].to_string(), "unexpected `;` at line 2, column 7");
+        assert!(matches!(errors[2], ParseError::UnexpectedEof { .. }));
+        assert_eq!(errors.len(), 3);
+    }
+
+    #[test]
+    fn test_parser_without_eof() {
+        let mut parser = Parser::new(vec![]);
+        assert_eq!(parser.parse_statements(), Ok(vec![]));
+        assert!(matches!(
+            Parser::new(vec![]).parse_expression(),
+            Err(ParseError::UnexpectedEof { .. })
+        ));
+
+        ["1 +", "x := 1\n  y ==", "f(abc,"]
+            .iter()
+            .for_each(|input| {
+                let mut tokens = tokenize(input).unwrap();
+                let eof = tokens.pop().unwrap();
+                let err = Parser::new(tokens).parse_statements().unwrap_err();
+                assert_eq!(
+                    err,
+                    ParseError::UnexpectedEof {
+                        span: eof.span,
+                        line: eof.line,
+                        column: eof.column
+                    },
+                    "{}",
+                    input
+                );
+            });
+    }
+
+    #[test]
+    fn test_expect_and_consume_if() {
+        let mut parser = Parser::new(tokenize("(a] 1").unwrap());
+        assert!(!parser.consume_if(TokenKind::RParen));
+        assert!(parser.consume_if(TokenKind::LParen));
+        assert_eq!(
+            parser.expect(TokenKind::Ident).unwrap().typ,
+            TokenType::Ident(Symbol::intern("a"))
+        );
+
+        // a failed expect doesn't consume the token
+        let err = parser.expect(TokenKind::RParen).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `)` but found `]` at line 1, column 3"
+        );
+        assert!(parser.consume_if(TokenKind::RBracket));
+
+        let err = parser.expect(TokenKind::Ident).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `identifier` but found `1` at line 1, column 5"
+        );
+        parser.expect(TokenKind::Int).unwrap();
+        parser.expect(TokenKind::EOF).unwrap();
+    }
+}
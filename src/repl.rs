@@ -0,0 +1,63 @@
+use crate::lexer::tokenize_with_errors;
+use crate::token::TokenType;
+use std::io::{self, BufRead, Write};
+
+const PROMPT: &str = ">> ";
+
+/// Reads lines from the standard input and prints their tokens until EOF.
+pub fn repl() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    run(stdin.lock(), stdout.lock())
+}
+
+/// Lexes every line read from `input`, writing the tokens and the errors found to `output`.
+pub fn run<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    write!(output, "{}", PROMPT)?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        let (tokens, errors) = tokenize_with_errors(&line);
+
+        let tokens: Vec<String> = tokens
+            .iter()
+            .filter(|t| t.typ != TokenType::EOF)
+            .map(|t| t.typ.to_string())
+            .collect();
+        writeln!(output, "{}", tokens.join(" "))?;
+
+        for err in errors {
+            writeln!(output, "error: {}", err)?;
+        }
+
+        write!(output, "{}", PROMPT)?;
+        output.flush()?;
+    }
+
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_run() {
+        let input = Cursor::new("a := 1 + 2.5\n\"hi\" 0b2\n");
+        let mut output = vec![];
+        run(input, &mut output).unwrap();
+
+        let expected = [
+            ">> a : = 1 + 2.5",
+            ">> \"hi\" 0",
+            "error: invalid number literal '0b2'",
+            ">> ",
+            "",
+        ]
+        .join("\n");
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
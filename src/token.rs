@@ -1,37 +1,867 @@
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub typ: TokenType,
+use crate::intern::Symbol;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token<'a> {
+    pub typ: TokenType<'a>,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+    /// Source text of number literals, like `0xFF` or `1_000u16`, which can't be
+    /// told from their value when reporting them. `None` for any other token.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub raw: Option<Cow<'a, str>>,
+}
+
+impl<'a> Token<'a> {
+    pub fn new(typ: TokenType<'a>, span: Span, line: usize, column: usize) -> Token<'a> {
+        Token {
+            typ,
+            span,
+            line,
+            column,
+            raw: None,
+        }
+    }
+
+    /// Returns the exact source text this token was lexed from.
+    pub fn lexeme<'s>(&self, src: &'s str) -> &'s str {
+        &src[self.span.start..self.span.end]
+    }
+
+    /// Copies any text borrowed from the input, so the token can outlive it.
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            typ: self.typ.into_owned(),
+            span: self.span,
+            line: self.line,
+            column: self.column,
+            raw: self.raw.map(|raw| Cow::Owned(raw.into_owned())),
+        }
+    }
 }
 
-impl Token {
-    pub fn new(typ: TokenType) -> Token {
-        Token { typ }
+impl<'a> fmt::Display for Token<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.typ {
+            TokenType::Illegal(ch) => write!(f, "illegal character `{}`", ch)?,
+            typ => write!(f, "`{}`", typ)?,
+        }
+
+        write!(f, " at line {}, column {}", self.line, self.column)
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum TokenType {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenType<'a> {
     Illegal(char),
     EOF,
+    /// the full text of a comment, only produced when `LexerOptions::keep_comments` is set
+    Comment(Cow<'a, str>),
+    /// a run of whitespace, only produced when `LexerOptions::lossless` is set
+    Whitespace(Cow<'a, str>),
+
+    Type(Cow<'a, str>),
 
-    Type(String),
+    If,
+    Else,
+    While,
+    For,
+    Fn,
+    Return,
+    Let,
+    Const,
+    Break,
+    Continue,
 
-    Ident(String),
-    Int(i32),
-    Float(f32),
+    Ident(Symbol),
+    Int(i64),
+    Float(f64),
+    /// an integer with an explicit type suffix like `10i64` or `255u8`
+    IntSuffixed(i64, NumberSuffix),
+    /// a float with an explicit type suffix like `2.5f32`
+    FloatSuffixed(f64, NumberSuffix),
     Bool(bool),
-    String(String),
+    String(Cow<'a, str>),
+    /// `"text {` the text before the first interpolated expression of a string
+    StringStart(Cow<'a, str>),
+    /// `} text {` the text between two interpolated expressions
+    StringMiddle(Cow<'a, str>),
+    /// `} text"` the text after the last interpolated expression
+    StringEnd(Cow<'a, str>),
+    Char(char),
+    /// `b"..."` a byte string
+    Bytes(Vec<u8>),
+    /// the absence of a value, spelled `null` in the source
+    Null,
 
     Assign,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    PercentAssign,
+    /// `?`, a ternary `a ? b : c` reuses `Colon` instead of having its own token
+    Question,
     Colon,
+    Comma,
+    Semicolon,
+    /// `@`, starts an annotation like `@deprecated`
+    At,
+    Dot,
+    DotDot,
+    DotDotEq,
+    Arrow,
+    FatArrow,
+
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
 
     Equal,
     NotEqual,
     Bang,
 
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+
+    And,
+    Or,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    Shl,
+    Shr,
+
     Minus,
     Plus,
     Asterisk,
+    /// `**`, exponentiation
+    Power,
     Slash,
-    Module,
+    Percent,
+}
+
+impl<'a> TokenType<'a> {
+    /// Copies any text borrowed from the input, so the token can outlive it.
+    pub fn into_owned(self) -> TokenType<'static> {
+        match self {
+            TokenType::Illegal(value) => TokenType::Illegal(value),
+            TokenType::EOF => TokenType::EOF,
+            TokenType::Comment(value) => TokenType::Comment(Cow::Owned(value.into_owned())),
+            TokenType::Whitespace(value) => TokenType::Whitespace(Cow::Owned(value.into_owned())),
+            TokenType::Type(value) => TokenType::Type(Cow::Owned(value.into_owned())),
+            TokenType::If => TokenType::If,
+            TokenType::Else => TokenType::Else,
+            TokenType::While => TokenType::While,
+            TokenType::For => TokenType::For,
+            TokenType::Fn => TokenType::Fn,
+            TokenType::Return => TokenType::Return,
+            TokenType::Let => TokenType::Let,
+            TokenType::Const => TokenType::Const,
+            TokenType::Break => TokenType::Break,
+            TokenType::Continue => TokenType::Continue,
+            TokenType::Ident(value) => TokenType::Ident(value),
+            TokenType::Int(value) => TokenType::Int(value),
+            TokenType::Float(value) => TokenType::Float(value),
+            TokenType::IntSuffixed(value, suffix) => TokenType::IntSuffixed(value, suffix),
+            TokenType::FloatSuffixed(value, suffix) => TokenType::FloatSuffixed(value, suffix),
+            TokenType::Bool(value) => TokenType::Bool(value),
+            TokenType::String(value) => TokenType::String(Cow::Owned(value.into_owned())),
+            TokenType::StringStart(value) => TokenType::StringStart(Cow::Owned(value.into_owned())),
+            TokenType::StringMiddle(value) => {
+                TokenType::StringMiddle(Cow::Owned(value.into_owned()))
+            }
+            TokenType::StringEnd(value) => TokenType::StringEnd(Cow::Owned(value.into_owned())),
+            TokenType::Char(value) => TokenType::Char(value),
+            TokenType::Bytes(value) => TokenType::Bytes(value),
+            TokenType::Null => TokenType::Null,
+            TokenType::Assign => TokenType::Assign,
+            TokenType::PlusAssign => TokenType::PlusAssign,
+            TokenType::MinusAssign => TokenType::MinusAssign,
+            TokenType::AsteriskAssign => TokenType::AsteriskAssign,
+            TokenType::SlashAssign => TokenType::SlashAssign,
+            TokenType::PercentAssign => TokenType::PercentAssign,
+            TokenType::Question => TokenType::Question,
+            TokenType::Colon => TokenType::Colon,
+            TokenType::Comma => TokenType::Comma,
+            TokenType::Semicolon => TokenType::Semicolon,
+            TokenType::At => TokenType::At,
+            TokenType::Dot => TokenType::Dot,
+            TokenType::DotDot => TokenType::DotDot,
+            TokenType::DotDotEq => TokenType::DotDotEq,
+            TokenType::Arrow => TokenType::Arrow,
+            TokenType::FatArrow => TokenType::FatArrow,
+            TokenType::LParen => TokenType::LParen,
+            TokenType::RParen => TokenType::RParen,
+            TokenType::LBrace => TokenType::LBrace,
+            TokenType::RBrace => TokenType::RBrace,
+            TokenType::LBracket => TokenType::LBracket,
+            TokenType::RBracket => TokenType::RBracket,
+            TokenType::Equal => TokenType::Equal,
+            TokenType::NotEqual => TokenType::NotEqual,
+            TokenType::Bang => TokenType::Bang,
+            TokenType::Lt => TokenType::Lt,
+            TokenType::Gt => TokenType::Gt,
+            TokenType::Lte => TokenType::Lte,
+            TokenType::Gte => TokenType::Gte,
+            TokenType::And => TokenType::And,
+            TokenType::Or => TokenType::Or,
+            TokenType::BitAnd => TokenType::BitAnd,
+            TokenType::BitOr => TokenType::BitOr,
+            TokenType::BitXor => TokenType::BitXor,
+            TokenType::BitNot => TokenType::BitNot,
+            TokenType::Shl => TokenType::Shl,
+            TokenType::Shr => TokenType::Shr,
+            TokenType::Minus => TokenType::Minus,
+            TokenType::Plus => TokenType::Plus,
+            TokenType::Asterisk => TokenType::Asterisk,
+            TokenType::Power => TokenType::Power,
+            TokenType::Slash => TokenType::Slash,
+            TokenType::Percent => TokenType::Percent,
+        }
+    }
+
+    /// Returns the variant of this token without its payload.
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            TokenType::Illegal(_) => TokenKind::Illegal,
+            TokenType::EOF => TokenKind::EOF,
+            TokenType::Comment(_) => TokenKind::Comment,
+            TokenType::Whitespace(_) => TokenKind::Whitespace,
+            TokenType::Type(_) => TokenKind::Type,
+            TokenType::If => TokenKind::If,
+            TokenType::Else => TokenKind::Else,
+            TokenType::While => TokenKind::While,
+            TokenType::For => TokenKind::For,
+            TokenType::Fn => TokenKind::Fn,
+            TokenType::Return => TokenKind::Return,
+            TokenType::Let => TokenKind::Let,
+            TokenType::Const => TokenKind::Const,
+            TokenType::Break => TokenKind::Break,
+            TokenType::Continue => TokenKind::Continue,
+            TokenType::Ident(_) => TokenKind::Ident,
+            TokenType::Int(_) => TokenKind::Int,
+            TokenType::Float(_) => TokenKind::Float,
+            TokenType::IntSuffixed(..) => TokenKind::IntSuffixed,
+            TokenType::FloatSuffixed(..) => TokenKind::FloatSuffixed,
+            TokenType::Bool(_) => TokenKind::Bool,
+            TokenType::String(_) => TokenKind::String,
+            TokenType::StringStart(_) => TokenKind::StringStart,
+            TokenType::StringMiddle(_) => TokenKind::StringMiddle,
+            TokenType::StringEnd(_) => TokenKind::StringEnd,
+            TokenType::Char(_) => TokenKind::Char,
+            TokenType::Bytes(_) => TokenKind::Bytes,
+            TokenType::Null => TokenKind::Null,
+            TokenType::Assign => TokenKind::Assign,
+            TokenType::PlusAssign => TokenKind::PlusAssign,
+            TokenType::MinusAssign => TokenKind::MinusAssign,
+            TokenType::AsteriskAssign => TokenKind::AsteriskAssign,
+            TokenType::SlashAssign => TokenKind::SlashAssign,
+            TokenType::PercentAssign => TokenKind::PercentAssign,
+            TokenType::Question => TokenKind::Question,
+            TokenType::Colon => TokenKind::Colon,
+            TokenType::Comma => TokenKind::Comma,
+            TokenType::Semicolon => TokenKind::Semicolon,
+            TokenType::At => TokenKind::At,
+            TokenType::Dot => TokenKind::Dot,
+            TokenType::DotDot => TokenKind::DotDot,
+            TokenType::DotDotEq => TokenKind::DotDotEq,
+            TokenType::Arrow => TokenKind::Arrow,
+            TokenType::FatArrow => TokenKind::FatArrow,
+            TokenType::LParen => TokenKind::LParen,
+            TokenType::RParen => TokenKind::RParen,
+            TokenType::LBrace => TokenKind::LBrace,
+            TokenType::RBrace => TokenKind::RBrace,
+            TokenType::LBracket => TokenKind::LBracket,
+            TokenType::RBracket => TokenKind::RBracket,
+            TokenType::Equal => TokenKind::Equal,
+            TokenType::NotEqual => TokenKind::NotEqual,
+            TokenType::Bang => TokenKind::Bang,
+            TokenType::Lt => TokenKind::Lt,
+            TokenType::Gt => TokenKind::Gt,
+            TokenType::Lte => TokenKind::Lte,
+            TokenType::Gte => TokenKind::Gte,
+            TokenType::And => TokenKind::And,
+            TokenType::Or => TokenKind::Or,
+            TokenType::BitAnd => TokenKind::BitAnd,
+            TokenType::BitOr => TokenKind::BitOr,
+            TokenType::BitXor => TokenKind::BitXor,
+            TokenType::BitNot => TokenKind::BitNot,
+            TokenType::Shl => TokenKind::Shl,
+            TokenType::Shr => TokenKind::Shr,
+            TokenType::Minus => TokenKind::Minus,
+            TokenType::Plus => TokenKind::Plus,
+            TokenType::Asterisk => TokenKind::Asterisk,
+            TokenType::Power => TokenKind::Power,
+            TokenType::Slash => TokenKind::Slash,
+            TokenType::Percent => TokenKind::Percent,
+        }
+    }
+}
+
+impl<'a> fmt::Display for TokenType<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenType::Illegal(ch) => write!(f, "{}", ch),
+            TokenType::EOF => write!(f, "EOF"),
+            TokenType::Comment(text) | TokenType::Whitespace(text) => write!(f, "{}", text),
+
+            TokenType::Type(name) => write!(f, "{}", name),
+
+            TokenType::If => write!(f, "if"),
+            TokenType::Else => write!(f, "else"),
+            TokenType::While => write!(f, "while"),
+            TokenType::For => write!(f, "for"),
+            TokenType::Fn => write!(f, "fn"),
+            TokenType::Return => write!(f, "return"),
+            TokenType::Let => write!(f, "let"),
+            TokenType::Const => write!(f, "const"),
+            TokenType::Break => write!(f, "break"),
+            TokenType::Continue => write!(f, "continue"),
+
+            TokenType::Ident(name) => write!(f, "{}", name),
+            TokenType::Int(value) => write!(f, "{}", value),
+            TokenType::Float(value) => write!(f, "{:?}", value),
+            TokenType::IntSuffixed(value, suffix) => write!(f, "{}{}", value, suffix),
+            TokenType::FloatSuffixed(value, suffix) => write!(f, "{:?}{}", value, suffix),
+            TokenType::Bool(value) => write!(f, "{}", value),
+            TokenType::String(value) => write!(f, "{:?}", value),
+            TokenType::StringStart(value) => write!(f, "\"{}{{", value.escape_debug()),
+            TokenType::StringMiddle(value) => write!(f, "}}{}{{", value.escape_debug()),
+            TokenType::StringEnd(value) => write!(f, "}}{}\"", value.escape_debug()),
+            TokenType::Char(value) => write!(f, "{:?}", value),
+            TokenType::Bytes(value) => {
+                let escaped: String = value
+                    .iter()
+                    .flat_map(|byte| std::ascii::escape_default(*byte))
+                    .map(char::from)
+                    .collect();
+                write!(f, "b\"{}\"", escaped)
+            }
+            TokenType::Null => write!(f, "null"),
+
+            TokenType::Assign => write!(f, "="),
+            TokenType::PlusAssign => write!(f, "+="),
+            TokenType::MinusAssign => write!(f, "-="),
+            TokenType::AsteriskAssign => write!(f, "*="),
+            TokenType::SlashAssign => write!(f, "/="),
+            TokenType::PercentAssign => write!(f, "%="),
+            TokenType::Question => write!(f, "?"),
+            TokenType::Colon => write!(f, ":"),
+            TokenType::Comma => write!(f, ","),
+            TokenType::Semicolon => write!(f, ";"),
+            TokenType::At => write!(f, "@"),
+            TokenType::Dot => write!(f, "."),
+            TokenType::DotDot => write!(f, ".."),
+            TokenType::DotDotEq => write!(f, "..="),
+            TokenType::Arrow => write!(f, "->"),
+            TokenType::FatArrow => write!(f, "=>"),
+
+            TokenType::LParen => write!(f, "("),
+            TokenType::RParen => write!(f, ")"),
+            TokenType::LBrace => write!(f, "{{"),
+            TokenType::RBrace => write!(f, "}}"),
+            TokenType::LBracket => write!(f, "["),
+            TokenType::RBracket => write!(f, "]"),
+
+            TokenType::Equal => write!(f, "=="),
+            TokenType::NotEqual => write!(f, "!="),
+            TokenType::Bang => write!(f, "!"),
+
+            TokenType::Lt => write!(f, "<"),
+            TokenType::Gt => write!(f, ">"),
+            TokenType::Lte => write!(f, "<="),
+            TokenType::Gte => write!(f, ">="),
+
+            TokenType::And => write!(f, "&&"),
+            TokenType::Or => write!(f, "||"),
+
+            TokenType::BitAnd => write!(f, "&"),
+            TokenType::BitOr => write!(f, "|"),
+            TokenType::BitXor => write!(f, "^"),
+            TokenType::BitNot => write!(f, "~"),
+            TokenType::Shl => write!(f, "<<"),
+            TokenType::Shr => write!(f, ">>"),
+
+            TokenType::Minus => write!(f, "-"),
+            TokenType::Plus => write!(f, "+"),
+            TokenType::Asterisk => write!(f, "*"),
+            TokenType::Power => write!(f, "**"),
+            TokenType::Slash => write!(f, "/"),
+            TokenType::Percent => write!(f, "%"),
+        }
+    }
+}
+
+/// The variant of a `TokenType` without its payload, so it can be hashed and compared
+/// regardless of the value of literals, like when grouping tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenKind {
+    Illegal,
+    EOF,
+    Comment,
+    Whitespace,
+
+    Type,
+
+    If,
+    Else,
+    While,
+    For,
+    Fn,
+    Return,
+    Let,
+    Const,
+    Break,
+    Continue,
+
+    Ident,
+    Int,
+    Float,
+    IntSuffixed,
+    FloatSuffixed,
+    Bool,
+    String,
+    StringStart,
+    StringMiddle,
+    StringEnd,
+    Char,
+    Bytes,
+    Null,
+
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    PercentAssign,
+    Question,
+    Colon,
+    Comma,
+    Semicolon,
+    At,
+    Dot,
+    DotDot,
+    DotDotEq,
+    Arrow,
+    FatArrow,
+
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+
+    Equal,
+    NotEqual,
+    Bang,
+
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+
+    And,
+    Or,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    Shl,
+    Shr,
+
+    Minus,
+    Plus,
+    Asterisk,
+    Power,
+    Slash,
+    Percent,
+}
+
+impl TokenKind {
+    /// Numbers, strings (including the pieces of an interpolated one), chars,
+    /// byte strings, `true`, `false` and `null`.
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Int
+                | TokenKind::Float
+                | TokenKind::IntSuffixed
+                | TokenKind::FloatSuffixed
+                | TokenKind::Bool
+                | TokenKind::String
+                | TokenKind::StringStart
+                | TokenKind::StringMiddle
+                | TokenKind::StringEnd
+                | TokenKind::Char
+                | TokenKind::Bytes
+                | TokenKind::Null
+        )
+    }
+
+    /// Arithmetic, comparison, logical, bitwise, assignment and range operators,
+    /// and the `?` of a ternary.
+    pub fn is_operator(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Assign
+                | TokenKind::PlusAssign
+                | TokenKind::MinusAssign
+                | TokenKind::AsteriskAssign
+                | TokenKind::SlashAssign
+                | TokenKind::PercentAssign
+                | TokenKind::Question
+                | TokenKind::DotDot
+                | TokenKind::DotDotEq
+                | TokenKind::Equal
+                | TokenKind::NotEqual
+                | TokenKind::Bang
+                | TokenKind::Lt
+                | TokenKind::Gt
+                | TokenKind::Lte
+                | TokenKind::Gte
+                | TokenKind::And
+                | TokenKind::Or
+                | TokenKind::BitAnd
+                | TokenKind::BitOr
+                | TokenKind::BitXor
+                | TokenKind::BitNot
+                | TokenKind::Shl
+                | TokenKind::Shr
+                | TokenKind::Minus
+                | TokenKind::Plus
+                | TokenKind::Asterisk
+                | TokenKind::Power
+                | TokenKind::Slash
+                | TokenKind::Percent
+        )
+    }
+
+    /// Reserved words. `true`, `false` and `null` are literals instead, and the
+    /// built-in type names are `Type`.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::If
+                | TokenKind::Else
+                | TokenKind::While
+                | TokenKind::For
+                | TokenKind::Fn
+                | TokenKind::Return
+                | TokenKind::Let
+                | TokenKind::Const
+                | TokenKind::Break
+                | TokenKind::Continue
+        )
+    }
+}
+
+// payload-less kinds are shown as their text like `TokenType` does,
+// and the rest by the name of what they hold
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Illegal => write!(f, "illegal character"),
+            TokenKind::EOF => write!(f, "EOF"),
+            TokenKind::Comment => write!(f, "comment"),
+            TokenKind::Whitespace => write!(f, "whitespace"),
+            TokenKind::Type => write!(f, "type"),
+            TokenKind::Ident => write!(f, "identifier"),
+            TokenKind::Int | TokenKind::IntSuffixed => write!(f, "int"),
+            TokenKind::Float | TokenKind::FloatSuffixed => write!(f, "float"),
+            TokenKind::Bool => write!(f, "bool"),
+            TokenKind::String
+            | TokenKind::StringStart
+            | TokenKind::StringMiddle
+            | TokenKind::StringEnd => write!(f, "string"),
+            TokenKind::Char => write!(f, "char"),
+            TokenKind::Bytes => write!(f, "byte string"),
+            TokenKind::Null => write!(f, "null"),
+
+            TokenKind::If => write!(f, "if"),
+            TokenKind::Else => write!(f, "else"),
+            TokenKind::While => write!(f, "while"),
+            TokenKind::For => write!(f, "for"),
+            TokenKind::Fn => write!(f, "fn"),
+            TokenKind::Return => write!(f, "return"),
+            TokenKind::Let => write!(f, "let"),
+            TokenKind::Const => write!(f, "const"),
+            TokenKind::Break => write!(f, "break"),
+            TokenKind::Continue => write!(f, "continue"),
+
+            TokenKind::Assign => write!(f, "="),
+            TokenKind::PlusAssign => write!(f, "+="),
+            TokenKind::MinusAssign => write!(f, "-="),
+            TokenKind::AsteriskAssign => write!(f, "*="),
+            TokenKind::SlashAssign => write!(f, "/="),
+            TokenKind::PercentAssign => write!(f, "%="),
+            TokenKind::Question => write!(f, "?"),
+            TokenKind::Colon => write!(f, ":"),
+            TokenKind::Comma => write!(f, ","),
+            TokenKind::Semicolon => write!(f, ";"),
+            TokenKind::At => write!(f, "@"),
+            TokenKind::Dot => write!(f, "."),
+            TokenKind::DotDot => write!(f, ".."),
+            TokenKind::DotDotEq => write!(f, "..="),
+            TokenKind::Arrow => write!(f, "->"),
+            TokenKind::FatArrow => write!(f, "=>"),
+
+            TokenKind::LParen => write!(f, "("),
+            TokenKind::RParen => write!(f, ")"),
+            TokenKind::LBrace => write!(f, "{{"),
+            TokenKind::RBrace => write!(f, "}}"),
+            TokenKind::LBracket => write!(f, "["),
+            TokenKind::RBracket => write!(f, "]"),
+
+            TokenKind::Equal => write!(f, "=="),
+            TokenKind::NotEqual => write!(f, "!="),
+            TokenKind::Bang => write!(f, "!"),
+
+            TokenKind::Lt => write!(f, "<"),
+            TokenKind::Gt => write!(f, ">"),
+            TokenKind::Lte => write!(f, "<="),
+            TokenKind::Gte => write!(f, ">="),
+
+            TokenKind::And => write!(f, "&&"),
+            TokenKind::Or => write!(f, "||"),
+
+            TokenKind::BitAnd => write!(f, "&"),
+            TokenKind::BitOr => write!(f, "|"),
+            TokenKind::BitXor => write!(f, "^"),
+            TokenKind::BitNot => write!(f, "~"),
+            TokenKind::Shl => write!(f, "<<"),
+            TokenKind::Shr => write!(f, ">>"),
+
+            TokenKind::Minus => write!(f, "-"),
+            TokenKind::Plus => write!(f, "+"),
+            TokenKind::Asterisk => write!(f, "*"),
+            TokenKind::Power => write!(f, "**"),
+            TokenKind::Slash => write!(f, "/"),
+            TokenKind::Percent => write!(f, "%"),
+        }
+    }
+}
+
+/// Explicit type given to a number literal, written right after its digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NumberSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumberSuffix {
+    pub fn from_name(name: &str) -> Option<NumberSuffix> {
+        let suffix = match name {
+            "i8" => NumberSuffix::I8,
+            "i16" => NumberSuffix::I16,
+            "i32" => NumberSuffix::I32,
+            "i64" => NumberSuffix::I64,
+            "u8" => NumberSuffix::U8,
+            "u16" => NumberSuffix::U16,
+            "u32" => NumberSuffix::U32,
+            "u64" => NumberSuffix::U64,
+            "f32" => NumberSuffix::F32,
+            "f64" => NumberSuffix::F64,
+            _ => return None,
+        };
+
+        Some(suffix)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumberSuffix::F32 | NumberSuffix::F64)
+    }
+
+    /// Returns true if `value` is representable by this type.
+    /// Values of `u64` above `i64::MAX` can't be represented by the int token.
+    pub fn fits(&self, value: i64) -> bool {
+        match self {
+            NumberSuffix::I8 => i8::try_from(value).is_ok(),
+            NumberSuffix::I16 => i16::try_from(value).is_ok(),
+            NumberSuffix::I32 => i32::try_from(value).is_ok(),
+            NumberSuffix::U8 => u8::try_from(value).is_ok(),
+            NumberSuffix::U16 => u16::try_from(value).is_ok(),
+            NumberSuffix::U32 => u32::try_from(value).is_ok(),
+            NumberSuffix::U64 => value >= 0,
+            NumberSuffix::I64 | NumberSuffix::F32 | NumberSuffix::F64 => true,
+        }
+    }
+
+    /// Returns true if the float `value` is representable by this type without
+    /// overflowing to infinity, int types never represent floats.
+    pub fn fits_float(&self, value: f64) -> bool {
+        match self {
+            NumberSuffix::F32 => value.is_finite() && value.abs() <= f64::from(f32::MAX),
+            NumberSuffix::F64 => value.is_finite(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for NumberSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            NumberSuffix::I8 => "i8",
+            NumberSuffix::I16 => "i16",
+            NumberSuffix::I32 => "i32",
+            NumberSuffix::I64 => "i64",
+            NumberSuffix::U8 => "u8",
+            NumberSuffix::U16 => "u16",
+            NumberSuffix::U32 => "u32",
+            NumberSuffix::U64 => "u64",
+            NumberSuffix::F32 => "f32",
+            NumberSuffix::F64 => "f64",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_display_token_type() {
+        let tests = [
+            (TokenType::Plus, "+"),
+            (TokenType::Int(10), "10"),
+            (TokenType::Float(1.0), "1.0"),
+            (TokenType::IntSuffixed(255, NumberSuffix::U8), "255u8"),
+            (TokenType::FloatSuffixed(2.5, NumberSuffix::F32), "2.5f32"),
+            (TokenType::Ident(Symbol::intern("x")), "x"),
+            (TokenType::String("say \"hi\"".into()), r#""say \"hi\"""#),
+            (TokenType::Char('a'), "'a'"),
+            (TokenType::Bytes(b"a\"\xff".to_vec()), r#"b"a\"\xff""#),
+            (TokenType::LBrace, "{"),
+            (TokenType::DotDotEq, "..="),
+            (TokenType::Fn, "fn"),
+            (TokenType::EOF, "EOF"),
+        ];
+
+        tests.iter().for_each(|(typ, expected)| {
+            assert_eq!(typ.to_string(), *expected);
+        });
+    }
+
+    #[test]
+    fn test_group_by_kind() {
+        let tokens = crate::lexer::tokenize("a + b + 1 + 2.5 + 3").unwrap();
+        let mut groups: HashMap<TokenKind, Vec<String>> = HashMap::new();
+        tokens.iter().for_each(|tok| {
+            groups
+                .entry(tok.typ.kind())
+                .or_default()
+                .push(tok.typ.to_string())
+        });
+
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[&TokenKind::Ident], ["a", "b"]);
+        assert_eq!(groups[&TokenKind::Plus].len(), 4);
+        assert_eq!(groups[&TokenKind::Int], ["1", "3"]);
+        assert_eq!(groups[&TokenKind::Float], ["2.5"]);
+        assert_eq!(groups[&TokenKind::EOF], ["EOF"]);
+    }
+
+    #[test]
+    fn test_token_kind_classification() {
+        let tests = [
+            // (input, literal, operator, keyword)
+            ("1", true, false, false),
+            ("2.5f32", true, false, false),
+            ("\"a {b} c\"", true, false, false),
+            ("null", true, false, false),
+            ("false", true, false, false),
+            ("+=", false, true, false),
+            ("..=", false, true, false),
+            ("&&", false, true, false),
+            ("while", false, false, true),
+            ("fn", false, false, true),
+            ("int", false, false, false),
+            ("x", false, false, false),
+            ("(", false, false, false),
+            (".", false, false, false),
+        ];
+
+        tests
+            .iter()
+            .for_each(|(input, literal, operator, keyword)| {
+                let kind = crate::lexer::tokenize(input).unwrap()[0].typ.kind();
+                assert_eq!(kind.is_literal(), *literal, "{}", input);
+                assert_eq!(kind.is_operator(), *operator, "{}", input);
+                assert_eq!(kind.is_keyword(), *keyword, "{}", input);
+            });
+
+        // every reserved word is either a literal or a keyword
+        crate::lexer::KEYWORDS.iter().for_each(|(word, typ)| {
+            assert!(
+                typ.kind().is_literal() != typ.kind().is_keyword(),
+                "{}",
+                word
+            );
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        let tokens = [
+            Token::new(TokenType::Ident(Symbol::intern("x")), Span::new(0, 1), 1, 1),
+            Token {
+                raw: Some("2.5".into()),
+                ..Token::new(TokenType::Float(2.5), Span::new(2, 5), 1, 3)
+            },
+            Token::new(TokenType::Illegal('#'), Span::new(6, 7), 2, 1),
+            Token::new(TokenType::EOF, Span::new(7, 7), 2, 2),
+        ];
+
+        let json = serde_json::to_string(&tokens).unwrap();
+        let decoded: Vec<Token> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded, tokens);
+
+        let json = serde_json::to_string(&TokenType::Illegal('#')).unwrap();
+        assert_eq!(json, r##"{"Illegal":"#"}"##);
+    }
 }
@@ -0,0 +1,488 @@
+use crate::ast::{BinaryOp, Block, Expr, PrefixOp, Stmt};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+    Char,
+    /// the type of a block without a final expression, it can't be spelled in the source
+    Null,
+}
+
+impl Type {
+    /// Returns the type spelled `name` in the source, like the ones in `lexer::TYPES`.
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "int" => Some(Type::Int),
+            "float" => Some(Type::Float),
+            "bool" => Some(Type::Bool),
+            "string" => Some(Type::String),
+            "char" => Some(Type::Char),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Char => write!(f, "char"),
+            Type::Null => write!(f, "null"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    Mismatch {
+        name: String,
+        expected: Type,
+        found: Type,
+    },
+    InvalidOperands {
+        op: BinaryOp,
+        left: Type,
+        right: Type,
+    },
+    InvalidOperand {
+        op: PrefixOp,
+        operand: Type,
+    },
+    UnknownType(String),
+    UndefinedVariable(String),
+    NotCallable(Type),
+    NotIndexable(Type),
+    UnknownField {
+        typ: Type,
+        field: String,
+    },
+    InvalidCondition(Type),
+    /// the body of a function doesn't evaluate to its declared return type
+    ReturnMismatch {
+        name: String,
+        expected: Type,
+        found: Type,
+    },
+    /// the branches of an `if` with an `else` have different types
+    BranchMismatch {
+        then: Type,
+        else_: Type,
+    },
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeError::Mismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` is declared as {} but initialized with {}",
+                name, expected, found
+            ),
+            TypeError::InvalidOperands { op, left, right } => write!(
+                f,
+                "unsupported operand types for `{}`: {} and {}",
+                op, left, right
+            ),
+            TypeError::InvalidOperand { op, operand } => {
+                write!(f, "unsupported operand type for `{}`: {}", op, operand)
+            }
+            TypeError::UnknownType(name) => write!(f, "unknown type `{}`", name),
+            TypeError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+            TypeError::NotCallable(typ) => write!(f, "values of type {} are not callable", typ),
+            TypeError::NotIndexable(typ) => write!(f, "values of type {} can't be indexed", typ),
+            TypeError::UnknownField { typ, field } => {
+                write!(f, "{} has no field `{}`", typ, field)
+            }
+            TypeError::InvalidCondition(typ) => {
+                write!(f, "expected a bool condition but found {}", typ)
+            }
+            TypeError::ReturnMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` should return {} but its body is {}",
+                name, expected, found
+            ),
+            TypeError::BranchMismatch { then, else_ } => write!(
+                f,
+                "`if` and `else` have different types: {} and {}",
+                then, else_
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// Checks that declarations match their annotated types and that operators
+/// get compatible operands, reporting every error found.
+pub fn typecheck(stmts: &[Stmt]) -> Result<(), Vec<TypeError>> {
+    let mut checker = Checker {
+        vars: HashMap::new(),
+        errors: vec![],
+    };
+
+    stmts.iter().for_each(|stmt| checker.check_stmt(stmt));
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+struct Checker {
+    vars: HashMap<String, Option<Type>>,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Decl {
+                name, typ, value, ..
+            } => {
+                let found = self.infer(value);
+                let expected = typ.as_ref().and_then(|typ| self.annotation(typ));
+
+                if let (Some(expected), Some(found)) = (expected, found) {
+                    if expected != found {
+                        self.errors.push(TypeError::Mismatch {
+                            name: name.clone(),
+                            expected,
+                            found,
+                        });
+                    }
+                }
+
+                // the annotation wins, so uses of a mistyped variable don't report again
+                self.vars.insert(name.clone(), expected.or(found));
+            }
+            Stmt::Assign { name, value } => {
+                let found = self.infer(value);
+                match self.vars.get(name) {
+                    Some(expected) => {
+                        if let (Some(expected), Some(found)) = (*expected, found) {
+                            if expected != found {
+                                self.errors.push(TypeError::Mismatch {
+                                    name: name.clone(),
+                                    expected,
+                                    found,
+                                });
+                            }
+                        }
+                    }
+                    None => self.errors.push(TypeError::UndefinedVariable(name.clone())),
+                }
+            }
+            Stmt::While { cond, body } => {
+                self.check_condition(cond);
+                self.infer_block(body);
+            }
+            Stmt::FnDecl {
+                name,
+                params,
+                return_type,
+                body,
+            } => {
+                // functions don't have a type yet, so calls to them are not checked
+                self.vars.insert(name.clone(), None);
+
+                let vars = self.vars.clone();
+                params.iter().for_each(|param| {
+                    let typ = param.typ.as_ref().and_then(|typ| self.annotation(typ));
+                    self.vars.insert(param.name.clone(), typ);
+                });
+
+                let expected = return_type.as_ref().and_then(|typ| self.annotation(typ));
+                let found = self.infer_block(body);
+                self.vars = vars;
+
+                if let (Some(expected), Some(found)) = (expected, found) {
+                    if expected != found {
+                        self.errors.push(TypeError::ReturnMismatch {
+                            name: name.clone(),
+                            expected,
+                            found,
+                        });
+                    }
+                }
+            }
+            Stmt::Break | Stmt::Continue => {}
+            Stmt::Expr(expr) => {
+                self.infer(expr);
+            }
+        }
+    }
+
+    // `None` means the type is unknown because an error was already reported
+    fn infer(&mut self, expr: &Expr) -> Option<Type> {
+        match expr {
+            Expr::Int(_) => Some(Type::Int),
+            Expr::Float(_) => Some(Type::Float),
+            Expr::Bool(_) => Some(Type::Bool),
+            Expr::Str(_) => Some(Type::String),
+            Expr::Ident(name) => match self.vars.get(name) {
+                Some(typ) => *typ,
+                None => {
+                    self.errors.push(TypeError::UndefinedVariable(name.clone()));
+                    None
+                }
+            },
+            Expr::Prefix { op, right } => {
+                let operand = self.infer(right)?;
+                let typ = match (op, operand) {
+                    (PrefixOp::Neg, Type::Int) | (PrefixOp::Neg, Type::Float) => Some(operand),
+                    (PrefixOp::Not, Type::Bool) => Some(Type::Bool),
+                    _ => None,
+                };
+
+                if typ.is_none() {
+                    self.errors
+                        .push(TypeError::InvalidOperand { op: *op, operand });
+                }
+                typ
+            }
+            Expr::Binary { op, left, right } => {
+                let (left, right) = (self.infer(left), self.infer(right));
+                let (left, right) = (left?, right?);
+                let typ = binary_type(*op, left, right);
+                if typ.is_none() {
+                    self.errors.push(TypeError::InvalidOperands {
+                        op: *op,
+                        left,
+                        right,
+                    });
+                }
+                typ
+            }
+            Expr::Call { callee, args } => {
+                let callee = self.infer(callee);
+                args.iter().for_each(|arg| {
+                    self.infer(arg);
+                });
+
+                if let Some(typ) = callee {
+                    self.errors.push(TypeError::NotCallable(typ));
+                }
+                None
+            }
+            Expr::Index { target, index } => {
+                let target = self.infer(target);
+                self.infer(index);
+
+                if let Some(typ) = target {
+                    self.errors.push(TypeError::NotIndexable(typ));
+                }
+                None
+            }
+            Expr::Block(block) => self.infer_block(block),
+            Expr::If { cond, then, else_ } => {
+                self.check_condition(cond);
+                let then = self.infer_block(then);
+                let else_ = match else_ {
+                    Some(else_) => self.infer(else_),
+                    // like the evaluator, without `else` the value is always `null`
+                    None => return Some(Type::Null),
+                };
+
+                let (then, else_) = (then?, else_?);
+                if then != else_ {
+                    self.errors.push(TypeError::BranchMismatch { then, else_ });
+                    return None;
+                }
+                Some(then)
+            }
+            Expr::Member { object, field } => {
+                let typ = self.infer(object)?;
+                self.errors.push(TypeError::UnknownField {
+                    typ,
+                    field: field.clone(),
+                });
+                None
+            }
+        }
+    }
+
+    // the type named by an annotation, reporting unknown names
+    fn annotation(&mut self, name: &str) -> Option<Type> {
+        let typ = Type::from_name(name);
+        if typ.is_none() {
+            self.errors.push(TypeError::UnknownType(name.to_string()));
+        }
+        typ
+    }
+
+    fn check_condition(&mut self, cond: &Expr) {
+        match self.infer(cond) {
+            Some(Type::Bool) | None => {}
+            Some(typ) => self.errors.push(TypeError::InvalidCondition(typ)),
+        }
+    }
+
+    fn infer_block(&mut self, block: &Block) -> Option<Type> {
+        // declarations inside the block are not visible after it
+        let vars = self.vars.clone();
+        block.stmts.iter().for_each(|stmt| self.check_stmt(stmt));
+        let typ = match &block.value {
+            Some(value) => self.infer(value),
+            None => Some(Type::Null),
+        };
+
+        self.vars = vars;
+        typ
+    }
+}
+
+// mirrors the rules of the evaluator, ints are promoted when mixed with floats
+fn binary_type(op: BinaryOp, left: Type, right: Type) -> Option<Type> {
+    use BinaryOp::*;
+
+    let numeric = |typ: Type| typ == Type::Int || typ == Type::Float;
+    match op {
+        Add if left == Type::String && right == Type::String => Some(Type::String),
+        Add | Sub | Mul | Div | Rem if numeric(left) && numeric(right) => {
+            if left == Type::Int && right == Type::Int {
+                Some(Type::Int)
+            } else {
+                Some(Type::Float)
+            }
+        }
+        BitAnd | BitOr | BitXor | Shl | Shr if left == Type::Int && right == Type::Int => {
+            Some(Type::Int)
+        }
+        And | Or if left == Type::Bool && right == Type::Bool => Some(Type::Bool),
+        Eq | NotEq if left == right => Some(Type::Bool),
+        Eq | NotEq | Lt | Gt | Lte | Gte if numeric(left) && numeric(right) => Some(Type::Bool),
+        Lt | Gt | Lte | Gte if left == Type::String && right == Type::String => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_program;
+
+    fn check(input: &str) -> Result<(), Vec<TypeError>> {
+        typecheck(&parse_program(input).unwrap())
+    }
+
+    #[test]
+    fn test_typecheck_valid_program() {
+        let input = r#"
+            x := 5
+            y: float = x * 0.5
+            let name: string = "isy" + "!"
+            const DEBUG: bool = !(x > 2) || y == 2.5
+            mask: int = x << 2 & 0xff
+            z: int = { y := 2; x * y }
+            w: string = if z > 2 { "big" } else if z < 0 { "negative" } else { "small" }
+            while x < 10 { x = x * 2 }
+            fn half(n: int) -> float { n / 2.0 }
+            half(x)
+            x + -y
+        "#;
+
+        assert_eq!(check(input), Ok(()));
+    }
+
+    #[test]
+    fn test_typecheck_declaration_mismatch() {
+        let errors = check(r#"x: int = "hi""#).unwrap_err();
+        assert_eq!(
+            errors,
+            [TypeError::Mismatch {
+                name: "x".to_string(),
+                expected: Type::Int,
+                found: Type::String,
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "`x` is declared as int but initialized with string"
+        );
+    }
+
+    #[test]
+    fn test_typecheck_reports_all_errors() {
+        let input = r#"
+            a: bool = 1
+            b := "a" - 1
+            c := !1.5
+            d: float = missing
+            a + d
+            a(missing)
+            d[0]
+            "hi".len
+            if 1 { 1 } else { 2.5 }
+            while "" { a = 2; b = 1; undefined = 1 }
+            fn f(n: int) -> bool { n }
+        "#;
+
+        let errors = check(input).unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                TypeError::Mismatch {
+                    name: "a".to_string(),
+                    expected: Type::Bool,
+                    found: Type::Int,
+                },
+                TypeError::InvalidOperands {
+                    op: BinaryOp::Sub,
+                    left: Type::String,
+                    right: Type::Int,
+                },
+                TypeError::InvalidOperand {
+                    op: PrefixOp::Not,
+                    operand: Type::Float,
+                },
+                TypeError::UndefinedVariable("missing".to_string()),
+                TypeError::InvalidOperands {
+                    op: BinaryOp::Add,
+                    left: Type::Bool,
+                    right: Type::Float,
+                },
+                TypeError::UndefinedVariable("missing".to_string()),
+                TypeError::NotCallable(Type::Bool),
+                TypeError::NotIndexable(Type::Float),
+                TypeError::UnknownField {
+                    typ: Type::String,
+                    field: "len".to_string(),
+                },
+                TypeError::InvalidCondition(Type::Int),
+                TypeError::BranchMismatch {
+                    then: Type::Int,
+                    else_: Type::Float,
+                },
+                TypeError::InvalidCondition(Type::String),
+                TypeError::Mismatch {
+                    name: "a".to_string(),
+                    expected: Type::Bool,
+                    found: Type::Int,
+                },
+                TypeError::UndefinedVariable("undefined".to_string()),
+                TypeError::ReturnMismatch {
+                    name: "f".to_string(),
+                    expected: Type::Bool,
+                    found: Type::Int,
+                },
+            ]
+        );
+    }
+}
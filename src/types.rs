@@ -0,0 +1,65 @@
+use crate::token::TokenType;
+use crate::typecheck::Type;
+
+/// Returns true if the literal `tok` is a valid initializer for a declaration of type `ty`,
+/// like `Bool` literals for `bool`. Tokens that aren't literals never match.
+pub fn type_matches_literal(ty: &str, tok: &TokenType) -> bool {
+    let ty = match Type::from_name(ty) {
+        Some(ty) => ty,
+        None => return false,
+    };
+
+    match tok {
+        TokenType::Bool(_) => ty == Type::Bool,
+        TokenType::Int(_) | TokenType::IntSuffixed(..) => ty == Type::Int,
+        TokenType::Float(_) | TokenType::FloatSuffixed(..) => ty == Type::Float,
+        // an interpolated string starts with `StringStart`
+        TokenType::String(_) | TokenType::StringStart(_) => ty == Type::String,
+        TokenType::Char(_) => ty == Type::Char,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intern::Symbol;
+    use crate::token::NumberSuffix;
+
+    #[test]
+    fn test_type_matches_literal() {
+        let tests = [
+            ("bool", TokenType::Bool(true)),
+            ("int", TokenType::Int(1)),
+            ("int", TokenType::IntSuffixed(255, NumberSuffix::U8)),
+            ("float", TokenType::Float(2.5)),
+            ("float", TokenType::FloatSuffixed(2.5, NumberSuffix::F64)),
+            ("string", TokenType::String("hi".into())),
+            ("string", TokenType::StringStart("hi ".into())),
+            ("char", TokenType::Char('a')),
+        ];
+
+        tests.iter().for_each(|(ty, tok)| {
+            assert!(type_matches_literal(ty, tok), "{} {:?}", ty, tok);
+        });
+    }
+
+    #[test]
+    fn test_type_mismatches_literal() {
+        let tests = [
+            ("bool", TokenType::Int(1)),
+            ("int", TokenType::Bool(false)),
+            ("int", TokenType::Float(1.0)),
+            ("float", TokenType::Int(1)),
+            ("string", TokenType::Char('a')),
+            ("char", TokenType::String("a".into())),
+            ("bool", TokenType::Ident(Symbol::intern("true_ish"))),
+            ("bool", TokenType::Null),
+            ("boolean", TokenType::Bool(true)),
+        ];
+
+        tests.iter().for_each(|(ty, tok)| {
+            assert!(!type_matches_literal(ty, tok), "{} {:?}", ty, tok);
+        });
+    }
+}
@@ -0,0 +1,113 @@
+use crate::ast::{Block, Expr, Stmt};
+
+/// Traverses the AST by reference. Every method walks into the children of
+/// its node by default, so a pass only overrides the nodes it cares about
+/// and calls the matching `walk_*` function to keep going deeper.
+pub trait Visitor {
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+}
+
+/// Visits the sub-expressions and blocks of `expr`, in source order.
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Int(_) | Expr::Float(_) | Expr::Bool(_) | Expr::Str(_) | Expr::Ident(_) => {}
+        Expr::Prefix { right, .. } => visitor.visit_expr(right),
+        Expr::Binary { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Call { callee, args } => {
+            visitor.visit_expr(callee);
+            args.iter().for_each(|arg| visitor.visit_expr(arg));
+        }
+        Expr::Index { target, index } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(index);
+        }
+        Expr::Member { object, .. } => visitor.visit_expr(object),
+        Expr::Block(block) => visitor.visit_block(block),
+        Expr::If { cond, then, else_ } => {
+            visitor.visit_expr(cond);
+            visitor.visit_block(then);
+            if let Some(else_) = else_ {
+                visitor.visit_expr(else_);
+            }
+        }
+    }
+}
+
+/// Visits the expressions and blocks of `stmt`, in source order.
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Decl { value, .. } | Stmt::Assign { value, .. } => visitor.visit_expr(value),
+        Stmt::While { cond, body } => {
+            visitor.visit_expr(cond);
+            visitor.visit_block(body);
+        }
+        Stmt::FnDecl { body, .. } => visitor.visit_block(body),
+        Stmt::Expr(expr) => visitor.visit_expr(expr),
+        Stmt::Break | Stmt::Continue => {}
+    }
+}
+
+/// Visits the statements of `block` and then its value.
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    block.stmts.iter().for_each(|stmt| visitor.visit_stmt(stmt));
+    if let Some(value) = &block.value {
+        visitor.visit_expr(value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_program;
+
+    #[derive(Default)]
+    struct BinaryCounter {
+        count: usize,
+    }
+
+    impl Visitor for BinaryCounter {
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let Expr::Binary { .. } = expr {
+                self.count += 1;
+            }
+
+            walk_expr(self, expr);
+        }
+    }
+
+    #[test]
+    fn test_count_binary_expressions() {
+        let tests = [
+            ("1", 0),
+            ("1 + 2 * 3", 2),
+            ("-(a - b)[i + 1].c", 2),
+            ("f(1 + 2, g(x) * 2)", 2),
+            ("x := 1 + 2; x = x * 2; x", 2),
+            ("if a > 1 { a + 1 } else if b { 2 } else { c - 1 }", 3),
+            ("while i < 3 { i = i + 1; if i == 2 { break } }", 3),
+            ("fn add(a, b) -> int { c := a + b; c * 1 } add(1, 2)", 2),
+        ];
+
+        tests.iter().for_each(|(input, expected)| {
+            let mut counter = BinaryCounter::default();
+            parse_program(input)
+                .unwrap()
+                .iter()
+                .for_each(|stmt| counter.visit_stmt(stmt));
+            assert_eq!(counter.count, *expected, "{}", input);
+        });
+    }
+}